@@ -0,0 +1,65 @@
+//! Per-frame cost of drawing a level into the buffer, with sprites spread
+//! along its length as the campaign does.
+
+use bevy::hierarchy::BuildChildren;
+
+const LEVEL_LENGTH: f32 = 10_000.0;
+
+fn level(sprites: usize) -> bevy::app::App {
+    let mut app = bevy::app::App::new();
+    app.add_plugins(bevy::transform::TransformPlugin)
+        .add_plugins(shellaga::buffer::plugin)
+        .add_plugins(shellaga::subcell::plugin)
+        .add_plugins(shellaga::sprite::plugin)
+        .add_plugins(shellaga::background::plugin)
+        .init_resource::<shellaga::frame::Size>();
+    app.add_systems(
+        bevy::app::Startup,
+        move |mut commands: bevy::ecs::system::Commands,
+              size: bevy::ecs::system::Res<shellaga::frame::Size>| {
+            let level = commands
+                .spawn((
+                    bevy::transform::TransformBundle::default(),
+                    shellaga::level::Level {
+                        length: LEVEL_LENGTH,
+                    },
+                ))
+                .id();
+            shellaga::background::spawn_layers(&mut commands, level);
+            commands
+                .spawn((
+                    shellaga::frame::Frame,
+                    bevy::transform::TransformBundle::default(),
+                    shellaga::collider::Collider::new(size.width as f32, size.height as f32),
+                ))
+                .set_parent(level);
+            for i in 0..sprites {
+                let x = i as f32 * LEVEL_LENGTH / sprites as f32;
+                let y = (i % size.height) as f32;
+                commands
+                    .spawn(shellaga::enemy::Enemy::bundle())
+                    .insert(bevy::transform::TransformBundle::from_transform(
+                        bevy::transform::components::Transform::from_xyz(x, y, 0.0),
+                    ))
+                    .set_parent(level);
+            }
+        },
+    );
+    // run startup and propagate the transforms
+    app.update();
+    app
+}
+
+fn frame(c: &mut criterion::Criterion) {
+    let mut group = c.benchmark_group("frame");
+    for sprites in [0, 100, 1000, 10_000] {
+        let mut app = level(sprites);
+        group.bench_function(criterion::BenchmarkId::new("sprites", sprites), |b| {
+            b.iter(|| app.update())
+        });
+    }
+    group.finish();
+}
+
+criterion::criterion_group!(benches, frame);
+criterion::criterion_main!(benches);
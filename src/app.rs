@@ -1,25 +1,152 @@
+/// Which part of the game is showing.
+#[derive(bevy::ecs::schedule::States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Screen {
+    #[default]
+    Title,
+    Playing,
+    /// Entering initials for a new high score.
+    NameEntry,
+    HighScores,
+    Settings,
+}
+
+impl Screen {
+    pub const ALL: [Screen; 5] = [
+        Screen::Title,
+        Screen::Playing,
+        Screen::NameEntry,
+        Screen::HighScores,
+        Screen::Settings,
+    ];
+}
+
+/// Screens and frame pacing, which every way of running the game shares.
 pub fn plugin(app: &mut bevy::app::App) {
-    app.add_systems(bevy::app::Update, handle_exit);
-}
-
-fn should_exit(event: &crate::terminal::TerminalEvent) -> bool {
-    match event {
-        crate::terminal::TerminalEvent::Key(crossterm::event::KeyEvent {
-            kind: crossterm::event::KeyEventKind::Press,
-            code: crossterm::event::KeyCode::Esc,
-            modifiers: _,
-            state: _,
-        }) => true,
-        _ => false,
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_state::<Screen>();
+    app.add_event::<crate::input::ActionEvent>();
+    app.init_resource::<FrameLimiter>();
+    for screen in Screen::ALL {
+        app.add_systems(bevy::ecs::schedule::OnEnter(screen), clear_actions);
+    }
+    app.add_systems(
+        bevy::app::Last,
+        limit_frame_rate.after(crate::terminal::render),
+    );
+}
+
+/// Quitting, pausing and the bell, for a game played in this terminal.
+pub fn local_plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::in_state, Condition, IntoSystemConfigs};
+
+    app.add_systems(
+        bevy::app::Update,
+        (
+            handle_exit.run_if(in_state(Screen::Title).or_else(in_state(Screen::Playing))),
+            handle_pause,
+        ),
+    );
+    app.add_systems(bevy::app::PostUpdate, render_pause.run_if(is_paused));
+    app.add_systems(
+        bevy::ecs::schedule::OnEnter(Screen::NameEntry),
+        ring_bell.run_if(sound_on),
+    );
+}
+
+/// When the next frame is due, to hold the frame rate to the one in the
+/// config.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+struct FrameLimiter {
+    next: Option<std::time::Instant>,
+}
+
+pub fn pressed(action: crate::input::Action) -> impl Fn(&crate::input::ActionEvent) -> bool {
+    move |event| event.action == action && event.pressed
+}
+
+/// Drop the actions from before the screen changed, which would otherwise
+/// still be waiting for the new screen's systems.
+fn clear_actions(
+    mut events: bevy::ecs::system::ResMut<bevy::ecs::event::Events<crate::input::ActionEvent>>,
+) {
+    events.clear();
+}
+
+fn limit_frame_rate(
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    mut limiter: bevy::ecs::system::ResMut<FrameLimiter>,
+) {
+    let period = std::time::Duration::from_secs_f64(1.0 / config.frame_rate.max(1) as f64);
+    let now = std::time::Instant::now();
+    let due = match limiter.next {
+        Some(next) if next > now => {
+            std::thread::sleep(next - now);
+            next
+        }
+        // running behind, so start again from now rather than rushing
+        _ => now,
+    };
+    limiter.next = Some(due + period);
+}
+
+pub fn sound_on(config: bevy::ecs::system::Res<crate::config::Config>) -> bool {
+    config.sound
+}
+
+/// The game is over.
+fn ring_bell(mut terminal: bevy::ecs::system::ResMut<crate::terminal::Terminal>) {
+    if let Err(e) = terminal.bell() {
+        log::error!("Failed to ring the bell: {}", e);
     }
 }
 
 fn handle_exit(
-    mut reader: bevy::ecs::event::EventReader<crate::terminal::TerminalEvent>,
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
     mut sender: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
 ) {
-    if reader.read().any(should_exit) {
+    if reader.read().any(pressed(crate::input::Action::Quit)) {
         sender.send(bevy::app::AppExit);
     }
 }
 
+fn handle_pause(
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
+) {
+    for _ in reader
+        .read()
+        .filter(|e| pressed(crate::input::Action::Pause)(e))
+    {
+        if time.is_paused() {
+            log::info!("Resuming");
+            time.unpause();
+        } else {
+            log::info!("Pausing");
+            time.pause();
+        }
+    }
+}
+
+fn is_paused(
+    time: bevy::ecs::system::Res<bevy::time::Time<bevy::time::Virtual>>,
+    bindings_screen: bevy::ecs::system::Res<crate::bindings_screen::BindingsScreen>,
+    console: Option<bevy::ecs::system::Res<crate::console::Console>>,
+) -> bool {
+    time.is_paused()
+        && !bindings_screen.is_open()
+        && !console.is_some_and(|console| console.is_open())
+}
+
+fn render_pause(mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>) {
+    overlays.push(|frame, area| {
+        let area = crate::terminal::centered(area, 12, 3);
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new("Paused")
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL)),
+            area,
+        );
+    });
+}
@@ -0,0 +1,377 @@
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::any_with_component, IntoSystemConfigs};
+
+    app.add_systems(
+        bevy::app::PostUpdate,
+        render
+            .run_if(any_with_component::<Layer>)
+            .run_if(any_with_component::<crate::frame::Frame>),
+    );
+}
+
+/// A procedurally generated background layer.
+///
+/// Layers are not made of entities. Each visible cell is sampled from a
+/// seeded hash of its layer space coordinate every frame, so only the
+/// screen currently in view is ever generated.
+#[derive(bevy::ecs::component::Component, Clone, Debug)]
+pub struct Layer {
+    /// How fast the layer scrolls relative to the frame:
+    /// 0.0 never moves, 1.0 moves with the level.
+    pub parallax: f32,
+    pub depth: f32,
+    pub seed: u64,
+    pub kind: LayerKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum LayerKind {
+    Stars {
+        density: f32,
+        character: char,
+        fg: crate::buffer::Color,
+    },
+    Nebula {
+        density: f32,
+        scale: f32,
+        character: char,
+        fg: crate::buffer::Color,
+    },
+    Terrain {
+        max_height: f32,
+        scale: f32,
+        fg: crate::buffer::Color,
+    },
+}
+
+impl Layer {
+    /// The cell of this layer which is visible at `column`, `row` of a frame
+    /// scrolled to `frame_x` and `height` cells tall.
+    pub fn sample(
+        &self,
+        frame_x: f32,
+        column: usize,
+        row: usize,
+        height: usize,
+    ) -> Option<crate::buffer::Cell> {
+        let x = layer_column(frame_x, self.parallax, column);
+        let y = row as i64;
+        let (character, fg) = match &self.kind {
+            LayerKind::Stars {
+                density,
+                character,
+                fg,
+            } => {
+                if unit(hash(self.seed, x, y)) >= *density {
+                    return None;
+                }
+                (*character, *fg)
+            }
+            LayerKind::Nebula {
+                density,
+                scale,
+                character,
+                fg,
+            } => {
+                let noise = value_noise_2d(self.seed, x as f32 / scale, y as f32 / (scale / 2.0));
+                if noise < 1.0 - density {
+                    return None;
+                }
+                (*character, *fg)
+            }
+            LayerKind::Terrain {
+                max_height,
+                scale,
+                fg,
+            } => {
+                let ground = |x: i64| {
+                    height as i64
+                        - 1
+                        - (value_noise_1d(self.seed, x as f32 / scale) * max_height) as i64
+                };
+                let top = ground(x);
+                if y < top {
+                    return None;
+                }
+                let character = if y > top {
+                    '#'
+                } else {
+                    match (ground(x - 1).cmp(&top), ground(x + 1).cmp(&top)) {
+                        (std::cmp::Ordering::Greater, std::cmp::Ordering::Greater) => '^',
+                        (std::cmp::Ordering::Greater, _) => '/',
+                        (_, std::cmp::Ordering::Greater) => '\\',
+                        _ => '_',
+                    }
+                };
+                (character, *fg)
+            }
+        };
+        Some(crate::buffer::Cell {
+            character: Some(character.into()),
+            fg,
+            depth: self.depth,
+            ..Default::default()
+        })
+    }
+}
+
+impl Layer {
+    /// The colour of the dot at `x`, `y` of a frame `height` cells tall
+    /// drawn with `scale` dots per cell, or None for layers which are only
+    /// drawn in cells.
+    pub fn sample_dot(
+        &self,
+        frame_x: f32,
+        x: usize,
+        y: usize,
+        scale: (usize, usize),
+        height: usize,
+    ) -> Option<Option<crate::buffer::Color>> {
+        let (scale_x, scale_y) = (scale.0 as f32, scale.1 as f32);
+        match &self.kind {
+            LayerKind::Stars { density, fg, .. } => {
+                let x = (frame_x * self.parallax * scale_x).floor() as i64 + x as i64;
+                // keep the same number of stars as in cells
+                let density = density / (scale_x * scale_y);
+                Some((unit(hash(self.seed, x, y as i64)) < density).then_some(*fg))
+            }
+            LayerKind::Terrain {
+                max_height,
+                scale,
+                fg,
+            } => {
+                let x = frame_x * self.parallax + (x as f32 + 0.5) / scale_x;
+                let top = height as f32 - value_noise_1d(self.seed, x / scale) * max_height - 1.0;
+                Some(((y as f32 + 0.5) / scale_y >= top).then_some(*fg))
+            }
+            LayerKind::Nebula { .. } => None,
+        }
+    }
+}
+
+pub fn spawn_layers(commands: &mut bevy::ecs::system::Commands, parent: bevy::ecs::entity::Entity) {
+    use bevy::hierarchy::BuildChildren;
+    let layers = [
+        Layer {
+            parallax: 0.1,
+            depth: 40.0,
+            seed: rand::random(),
+            kind: LayerKind::Nebula {
+                density: 0.3,
+                scale: 24.0,
+                character: '░',
+                fg: crate::buffer::Color::Magenta,
+            },
+        },
+        Layer {
+            parallax: 0.25,
+            depth: 30.0,
+            seed: rand::random(),
+            kind: LayerKind::Stars {
+                density: 0.02,
+                character: '.',
+                fg: crate::buffer::Color::DarkGray,
+            },
+        },
+        Layer {
+            parallax: 0.5,
+            depth: 20.0,
+            seed: rand::random(),
+            kind: LayerKind::Stars {
+                density: 0.01,
+                character: '*',
+                fg: crate::buffer::Color::White,
+            },
+        },
+        Layer {
+            parallax: 1.0,
+            depth: 10.0,
+            seed: rand::random(),
+            kind: LayerKind::Terrain {
+                max_height: 4.0,
+                scale: 12.0,
+                fg: crate::buffer::Color::Green,
+            },
+        },
+    ];
+    for layer in layers {
+        commands.spawn(layer).set_parent(parent);
+    }
+}
+
+fn render(
+    mut buffer: bevy::ecs::system::ResMut<crate::buffer::Buffer>,
+    resolution: bevy::ecs::system::Res<crate::subcell::Resolution>,
+    mut dots: bevy::ecs::system::ResMut<crate::subcell::Dots>,
+    layer_query: bevy::ecs::system::Query<&Layer>,
+    frame_query: bevy::ecs::system::Query<
+        (
+            &bevy::transform::components::GlobalTransform,
+            &crate::collider::Collider,
+        ),
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    let Ok((frame_transform, frame_collider)) = frame_query.get_single() else {
+        log::error!("Could not get unique frame");
+        return;
+    };
+
+    let frame_x = frame_transform.translation().x;
+    let (width, height) = (frame_collider.x as usize, frame_collider.y as usize);
+    for layer in &layer_query {
+        if !resolution.is_fine() || !render_to_dots(layer, frame_x, height, *resolution, &mut dots)
+        {
+            render_to_buffer(layer, frame_x, width, height, &mut buffer);
+        }
+    }
+}
+
+/// Returns false if the layer can't be drawn in dots.
+fn render_to_dots(
+    layer: &Layer,
+    frame_x: f32,
+    height: usize,
+    resolution: crate::subcell::Resolution,
+    dots: &mut crate::subcell::Dots,
+) -> bool {
+    let (rows, columns) = dots.0.dim();
+    for (y, x) in itertools::iproduct!(0..rows, 0..columns) {
+        match layer.sample_dot(frame_x, x, y, resolution.scale(), height) {
+            None => return false,
+            Some(None) => {}
+            Some(Some(fg)) => dots.plot(
+                x,
+                y,
+                crate::subcell::Dot {
+                    fg,
+                    depth: layer.depth,
+                },
+            ),
+        }
+    }
+    true
+}
+
+fn render_to_buffer(
+    layer: &Layer,
+    frame_x: f32,
+    width: usize,
+    height: usize,
+    buffer: &mut crate::buffer::Buffer,
+) {
+    use itertools::Itertools;
+    for (row, col) in (0..height).cartesian_product(0..width) {
+        let Some(cell) = buffer.0.get_mut([row, col]) else {
+            continue;
+        };
+        if layer.depth > cell.depth {
+            continue;
+        }
+        if let Some(layer_cell) = layer.sample(frame_x, col, row, height) {
+            *cell = layer_cell;
+        }
+    }
+}
+
+fn layer_column(frame_x: f32, parallax: f32, column: usize) -> i64 {
+    (frame_x * parallax).floor() as i64 + column as i64
+}
+
+fn hash(seed: u64, x: i64, y: i64) -> u64 {
+    // splitmix64 finaliser over the combined coordinates
+    let mut z = seed
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn unit(hash: u64) -> f32 {
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn value_noise_1d(seed: u64, x: f32) -> f32 {
+    let x0 = x.floor();
+    let t = smoothstep(x - x0);
+    let a = unit(hash(seed, x0 as i64, 0));
+    let b = unit(hash(seed, x0 as i64 + 1, 0));
+    a + (b - a) * t
+}
+
+fn value_noise_2d(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let corner = |dx: i64, dy: i64| unit(hash(seed, x0 as i64 + dx, y0 as i64 + dy));
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn stars(parallax: f32) -> Layer {
+        Layer {
+            parallax,
+            depth: 0.0,
+            seed: 1234,
+            kind: LayerKind::Stars {
+                density: 0.5,
+                character: '*',
+                fg: Default::default(),
+            },
+        }
+    }
+
+    fn row(layer: &Layer, frame_x: f32) -> Vec<Option<crate::buffer::Cell>> {
+        (0..16)
+            .map(|col| layer.sample(frame_x, col, 0, 1))
+            .collect()
+    }
+
+    #[test]
+    fn sample_is_deterministic() {
+        let layer = stars(0.5);
+        assert_eq!(row(&layer, 3.0), row(&layer, 3.0));
+    }
+
+    #[test]
+    fn static_layer_does_not_scroll() {
+        let layer = stars(0.0);
+        assert_eq!(row(&layer, 0.0), row(&layer, 100.0));
+    }
+
+    #[test]
+    fn layer_scrolls_at_parallax_rate() {
+        let layer = stars(0.5);
+        let before = row(&layer, 0.0);
+        let after = row(&layer, 4.0);
+        assert_eq!(before[2..], after[..14]);
+    }
+
+    #[test]
+    fn terrain_fills_from_bottom_edge() {
+        let layer = Layer {
+            parallax: 1.0,
+            depth: 0.0,
+            seed: 1234,
+            kind: LayerKind::Terrain {
+                max_height: 3.0,
+                scale: 4.0,
+                fg: Default::default(),
+            },
+        };
+        for col in 0..16 {
+            assert!(layer.sample(0.0, col, 0, 8).is_none());
+            assert!(layer.sample(0.0, col, 7, 8).is_some());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::input::{Action, Bindings, Key};
+
+/// In-game screen to view and rebind keys.
+///
+/// While it's open the game is paused and it receives every key event in
+/// place of the action translation in `input`.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct BindingsScreen {
+    open: bool,
+    selected: usize,
+    capturing: bool,
+    was_paused: bool,
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<BindingsScreen>();
+    app.add_systems(bevy::app::PostUpdate, render.run_if(is_open));
+}
+
+fn is_open(screen: bevy::ecs::system::Res<BindingsScreen>) -> bool {
+    screen.open
+}
+
+impl BindingsScreen {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, time: &mut bevy::time::Time<bevy::time::Virtual>) {
+        log::info!("Opening key bindings");
+        self.open = true;
+        self.capturing = false;
+        self.was_paused = time.is_paused();
+        time.pause();
+    }
+
+    pub fn close(&mut self, time: &mut bevy::time::Time<bevy::time::Virtual>) {
+        log::info!("Closing key bindings");
+        self.open = false;
+        if !self.was_paused {
+            time.unpause();
+        }
+    }
+
+    /// Returns false once the screen wants to close.
+    pub fn handle_key(
+        &mut self,
+        key: &crossterm::event::KeyEvent,
+        bindings: &mut Bindings,
+    ) -> bool {
+        use crossterm::event::KeyCode::*;
+
+        if key.kind != crossterm::event::KeyEventKind::Press {
+            return true;
+        }
+
+        let action = Action::ALL[self.selected];
+        if self.capturing {
+            self.capturing = false;
+            // escape cancels so the quit key can't be stolen by accident
+            if key.code != Esc {
+                bindings.bind(action, Key(key.code));
+            }
+            return true;
+        }
+
+        match key.code {
+            Up | Char('k') => {
+                self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len();
+            }
+            Down | Char('j') => {
+                self.selected = (self.selected + 1) % Action::ALL.len();
+            }
+            Enter => self.capturing = true,
+            Backspace | Delete if action != Action::Quit => bindings.clear(action),
+            Char('r') => *bindings = Bindings::default(),
+            Esc => return false,
+            code => return bindings.action(code) != Some(Action::Bindings),
+        }
+        true
+    }
+}
+
+fn render(
+    screen: bevy::ecs::system::Res<BindingsScreen>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    use itertools::Itertools;
+
+    let rows: Vec<_> = Action::ALL
+        .iter()
+        .map(|action| {
+            (
+                action.name(),
+                config.bindings.keys(*action).iter().join(", "),
+            )
+        })
+        .collect();
+    let selected = screen.selected;
+    let footer = if screen.capturing {
+        format!(" Press a key for {} ", Action::ALL[selected].name())
+    } else {
+        " Enter bind  Del clear  r reset  Esc close ".to_string()
+    };
+
+    overlays.push(move |frame, area| {
+        let area = crate::terminal::centered(area, 50, rows.len() as u16 + 2);
+        let table = ratatui::widgets::Table::new(
+            rows.into_iter().enumerate().map(|(i, (name, keys))| {
+                let row = ratatui::widgets::Row::new([name.to_string(), keys]);
+                if i == selected {
+                    row.style(
+                        ratatui::style::Style::new()
+                            .add_modifier(ratatui::style::Modifier::REVERSED),
+                    )
+                } else {
+                    row
+                }
+            }),
+            [
+                ratatui::layout::Constraint::Length(14),
+                ratatui::layout::Constraint::Fill(1),
+            ],
+        )
+        .block(
+            ratatui::widgets::Block::default()
+                .title(" Key Bindings ")
+                .title(
+                    ratatui::widgets::block::Title::from(footer)
+                        .position(ratatui::widgets::block::Position::Bottom),
+                )
+                .borders(ratatui::widgets::Borders::ALL),
+        );
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(table, area);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn press(code: crossterm::event::KeyCode) -> crossterm::event::KeyEvent {
+        crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn rebind_selected_action() {
+        use crossterm::event::KeyCode::*;
+        let mut screen = BindingsScreen::default();
+        let mut bindings = Bindings::default();
+
+        // select fire, clear it, then bind x
+        for code in [Down, Down, Down, Down, Backspace, Enter, Char('x')] {
+            assert!(screen.handle_key(&press(code), &mut bindings));
+        }
+
+        assert_eq!(bindings.keys(Action::Fire), &[Key(Char('x'))]);
+    }
+
+    #[test]
+    fn escape_cancels_capture() {
+        use crossterm::event::KeyCode::*;
+        let mut screen = BindingsScreen::default();
+        let mut bindings = Bindings::default();
+
+        assert!(screen.handle_key(&press(Enter), &mut bindings));
+        assert!(screen.handle_key(&press(Esc), &mut bindings));
+
+        assert_eq!(bindings, Bindings::default());
+        assert!(!screen.handle_key(&press(Esc), &mut bindings));
+    }
+
+    #[test]
+    fn quit_cannot_be_cleared() {
+        use crossterm::event::KeyCode::*;
+        let mut screen = BindingsScreen::default();
+        let mut bindings = Bindings::default();
+
+        for code in [Up, Up, Delete] {
+            assert!(screen.handle_key(&press(code), &mut bindings));
+        }
+
+        assert_eq!(Action::ALL[screen.selected], Action::Quit);
+        assert_eq!(bindings.keys(Action::Quit), &[Key(Esc)]);
+    }
+}
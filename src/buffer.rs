@@ -1,6 +1,9 @@
 #[allow(dead_code)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub enum Color {
+    /// Whatever the terminal draws by default.
+    #[default]
+    Reset,
     Black,
     Red,
     Green,
@@ -19,9 +22,70 @@ pub enum Color {
     White,
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Self::Black
+/// Longest grapheme cluster a glyph can hold, in bytes of UTF-8.
+const GLYPH_CAPACITY: usize = 23;
+
+/// A grapheme cluster, stored inline so cells stay `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    bytes: [u8; GLYPH_CAPACITY],
+    len: u8,
+}
+
+impl Glyph {
+    /// Fills the cell to the right of a double width glyph.
+    pub const CONTINUATION: Self = Self {
+        bytes: [0; GLYPH_CAPACITY],
+        len: 0,
+    };
+
+    /// None if the cluster is too long to store.
+    pub fn new(cluster: &str) -> Option<Self> {
+        if cluster.len() > GLYPH_CAPACITY {
+            return None;
+        }
+        let mut bytes = [0; GLYPH_CAPACITY];
+        bytes[..cluster.len()].copy_from_slice(cluster.as_bytes());
+        Some(Self {
+            bytes,
+            len: cluster.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).expect("glyphs are valid UTF-8")
+    }
+
+    /// Columns the glyph takes up in the terminal.
+    pub fn width(&self) -> usize {
+        unicode_width::UnicodeWidthStr::width(self.as_str())
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<char> for Glyph {
+    fn from(c: char) -> Self {
+        let mut bytes = [0; GLYPH_CAPACITY];
+        let len = c.encode_utf8(&mut bytes).len();
+        Self {
+            bytes,
+            len: len as u8,
+        }
+    }
+}
+
+impl std::fmt::Debug for Glyph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl std::fmt::Display for Glyph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -30,20 +94,102 @@ pub struct Cell {
     pub fg: Color,
     pub bg: Color,
     pub depth: f32,
-    pub character: Option<char>,
+    pub character: Option<Glyph>,
 }
 
 #[derive(bevy::ecs::system::Resource, PartialEq, Debug, Clone, Default)]
 pub struct Buffer(pub ndarray::Array2<Cell>);
 
+/// The cells which changed since the last frame drawn.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Damage {
+    previous: ndarray::Array2<Cell>,
+    /// The rows and columns bounding every changed cell, None if nothing
+    /// changed.
+    pub dirty: Option<(std::ops::Range<usize>, std::ops::Range<usize>)>,
+}
+
+impl Damage {
+    /// Compare the buffer against the last frame drawn.
+    pub fn update(&mut self, buffer: &Buffer) {
+        let (rows, columns) = buffer.0.dim();
+        if self.previous.dim() != buffer.0.dim() {
+            self.dirty = Some((0..rows, 0..columns));
+            return;
+        }
+
+        let mut dirty: Option<(std::ops::Range<usize>, std::ops::Range<usize>)> = None;
+        ndarray::Zip::indexed(&self.previous)
+            .and(&buffer.0)
+            .for_each(|(row, col), previous, cell| {
+                if previous == cell {
+                    return;
+                }
+                dirty = Some(match dirty.take() {
+                    None => (row..row + 1, col..col + 1),
+                    Some((rows, columns)) => (
+                        rows.start.min(row)..rows.end.max(row + 1),
+                        columns.start.min(col)..columns.end.max(col + 1),
+                    ),
+                });
+            });
+        self.dirty = dirty;
+    }
+
+    /// Remember the buffer once it's been drawn, so a frame which failed to
+    /// draw is compared against what's really on the screen.
+    pub fn drawn(&mut self, buffer: &Buffer) {
+        self.previous.clone_from(&buffer.0);
+    }
+}
+
 pub fn plugin(app: &mut bevy::app::App) {
-    app.insert_resource::<Buffer>(Buffer(ndarray::Array2::from_elem((75, 128), Cell::default())));
+    app.init_resource::<Buffer>();
+    app.init_resource::<Damage>();
     app.add_systems(bevy::app::First, clear_buffer);
 }
 
-pub fn clear_buffer(mut buffer: bevy::ecs::system::ResMut<Buffer>) {
-    buffer.0 = buffer.0.map(|_| Cell {
+pub fn clear_buffer(
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    mut buffer: bevy::ecs::system::ResMut<Buffer>,
+) {
+    let cleared = Cell {
         depth: f32::MAX,
         ..Default::default()
-    });
+    };
+    // reuse the allocation unless the size has changed
+    if buffer.0.dim() == (size.height, size.width) {
+        buffer.0.fill(cleared);
+    } else {
+        buffer.0 = ndarray::Array2::from_elem((size.height, size.width), cleared);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn damage_bounds_changed_cells() {
+        let mut buffer = Buffer(ndarray::Array2::from_elem((4, 6), Cell::default()));
+        let mut damage = Damage::default();
+        damage.update(&buffer);
+        assert_eq!(damage.dirty, Some((0..4, 0..6)));
+        damage.drawn(&buffer);
+
+        damage.update(&buffer);
+        assert_eq!(damage.dirty, None);
+
+        buffer.0[[1, 4]].character = Some('x'.into());
+        buffer.0[[2, 2]].character = Some('y'.into());
+        damage.update(&buffer);
+        assert_eq!(damage.dirty, Some((1..3, 2..5)));
+        // until it's drawn, the change is still there
+        damage.update(&buffer);
+        assert_eq!(damage.dirty, Some((1..3, 2..5)));
+        damage.drawn(&buffer);
+        damage.update(&buffer);
+        assert_eq!(damage.dirty, None);
+    }
 }
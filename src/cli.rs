@@ -0,0 +1,65 @@
+#[derive(clap::Parser, Clone, Debug)]
+#[command(version, about)]
+pub struct Args {
+    /// Which game mode to play
+    #[arg(long, value_enum, default_value_t)]
+    pub mode: crate::level::Mode,
+
+    /// How finely to draw the background and shots
+    #[arg(long, value_enum, default_value_t)]
+    pub resolution: crate::subcell::Resolution,
+
+    /// Steer with the mouse pointer and fire with the left button
+    #[arg(long)]
+    pub mouse: bool,
+
+    /// Play field width in cells, fits the terminal by default
+    #[arg(long)]
+    pub width: Option<usize>,
+
+    /// Play field height in cells, fits the terminal by default
+    #[arg(long)]
+    pub height: Option<usize>,
+
+    /// Stream the game to `watch` on this TCP address, or Unix socket path
+    #[arg(long)]
+    pub spectate: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Host a game over the network, without a terminal of its own
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = crate::net::DEFAULT_ADDRESS)]
+        address: String,
+
+        /// Players to wait for before each game starts
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=8))]
+        players: u8,
+    },
+    /// Play in a game hosted with `serve`
+    Join {
+        /// Address of the server
+        #[arg(default_value = crate::net::DEFAULT_ADDRESS)]
+        address: String,
+    },
+    /// Host a game of its own for each player who connects with telnet
+    Host {
+        /// Address to listen on
+        #[arg(long, default_value = crate::session::DEFAULT_ADDRESS)]
+        address: String,
+
+        /// Games to run at once, turning away anyone else
+        #[arg(long, default_value_t = 8)]
+        sessions: usize,
+    },
+    /// Watch a game streamed with `--spectate`
+    Watch {
+        /// TCP address or Unix socket path the game streams to
+        address: String,
+    },
+}
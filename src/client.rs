@@ -0,0 +1,196 @@
+use crate::input::Action;
+use crate::net::{Connection, ToClient, ToServer};
+
+/// How long to wait for the server to answer when joining.
+const JOIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Status {
+    Waiting { joined: usize, needed: usize },
+    Playing,
+    Over { score: u32 },
+    Disconnected,
+}
+
+/// A game played on a server: this terminal only sends the player's
+/// actions and draws the frames which come back.
+#[derive(bevy::ecs::system::Resource)]
+pub struct Client {
+    connection: Connection,
+    player: usize,
+    status: Status,
+    /// Each player's lives, from the last frame.
+    lives: Vec<u32>,
+}
+
+/// Join the server at `address`, returning the size of its play field.
+pub fn connect(address: &str) -> Result<(Client, crate::frame::Size), Box<dyn std::error::Error>> {
+    let stream = std::net::TcpStream::connect(address)?;
+    let mut connection = Connection::new(stream)?;
+    match connection.wait::<ToClient>(JOIN_TIMEOUT)? {
+        ToClient::Welcome {
+            version,
+            player,
+            width,
+            height,
+        } => {
+            if version != env!("CARGO_PKG_VERSION") {
+                return Err(format!(
+                    "server runs version {}, this is {}",
+                    version,
+                    env!("CARGO_PKG_VERSION")
+                )
+                .into());
+            }
+            log::info!("Joined {} as player {}", address, player + 1);
+            let client = Client {
+                connection,
+                player,
+                status: Status::Waiting {
+                    joined: 0,
+                    needed: 0,
+                },
+                lives: Vec::new(),
+            };
+            Ok((client, crate::frame::Size { width, height }))
+        }
+        ToClient::Refused { reason } => Err(format!("server refused: {}", reason).into()),
+        message => Err(format!("unexpected message from server: {:?}", message).into()),
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    app.init_resource::<crate::buffer::Buffer>();
+    app.init_resource::<crate::buffer::Damage>();
+    app.init_resource::<crate::score::Score>();
+    app.init_resource::<crate::player::Players>();
+    app.add_systems(bevy::app::Startup, size_buffer);
+    app.add_systems(bevy::app::PreUpdate, receive);
+    app.add_systems(bevy::app::Update, forward);
+    app.add_systems(bevy::app::PostUpdate, render);
+}
+
+fn size_buffer(
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    mut buffer: bevy::ecs::system::ResMut<crate::buffer::Buffer>,
+) {
+    buffer.0 = ndarray::Array2::from_elem((size.height, size.width), Default::default());
+}
+
+fn receive(
+    mut client: bevy::ecs::system::ResMut<Client>,
+    mut buffer: bevy::ecs::system::ResMut<crate::buffer::Buffer>,
+    mut score: bevy::ecs::system::ResMut<crate::score::Score>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    mut terminal: bevy::ecs::system::ResMut<crate::terminal::Terminal>,
+) {
+    use bevy::ecs::change_detection::DetectChangesMut;
+
+    if client.status == Status::Disconnected {
+        return;
+    }
+    let messages = match client.connection.receive::<ToClient>() {
+        Ok(messages) => messages,
+        Err(e) => {
+            log::error!("Lost the server: {}", e);
+            client.status = Status::Disconnected;
+            return;
+        }
+    };
+    for message in messages {
+        match message {
+            ToClient::Waiting { joined, needed } => {
+                client.status = Status::Waiting { joined, needed };
+            }
+            ToClient::Frame {
+                changes,
+                score: latest,
+                lives,
+            } => {
+                crate::net::apply(&mut buffer, &changes);
+                score.set_if_neq(latest);
+                client.lives = lives;
+                client.status = Status::Playing;
+            }
+            ToClient::GameOver { score } => {
+                log::info!("Game over with {} points", score);
+                client.status = Status::Over { score };
+                if config.sound {
+                    if let Err(e) = terminal.bell() {
+                        log::error!("Failed to ring the bell: {}", e);
+                    }
+                }
+            }
+            message => log::warn!("Unexpected message from server: {:?}", message),
+        }
+    }
+    if client.connection.is_closed() && !matches!(client.status, Status::Over { .. }) {
+        log::error!("Server hung up");
+        client.status = Status::Disconnected;
+    }
+}
+
+/// Send the player's actions on to the server.
+fn forward(
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut client: bevy::ecs::system::ResMut<Client>,
+    mut exit: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
+) {
+    for event in reader.read() {
+        let finished = matches!(client.status, Status::Over { .. } | Status::Disconnected);
+        if event.pressed
+            && (event.action == Action::Quit || finished && event.action == Action::Fire)
+        {
+            exit.send(bevy::app::AppExit);
+            return;
+        }
+        if finished {
+            continue;
+        }
+        let message = ToServer::Action {
+            action: event.action,
+            pressed: event.pressed,
+        };
+        if let Err(e) = client.connection.send(&message) {
+            log::error!("Lost the server: {}", e);
+            client.status = Status::Disconnected;
+        }
+    }
+}
+
+fn render(
+    client: bevy::ecs::system::Res<Client>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    mut hud: bevy::ecs::system::ResMut<crate::terminal::Hud>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    hud.0 = (!client.lives.is_empty()).then(|| {
+        let lives = &client.lives;
+        crate::player::hud(lives.len(), |player| lives[player], &score)
+    });
+    let message = match client.status {
+        Status::Waiting { joined, needed } => Some(format!(
+            "You are player {}\nWaiting for players {}/{}",
+            client.player + 1,
+            joined,
+            needed
+        )),
+        Status::Playing => None,
+        Status::Over { score } => Some(format!("Game over\n{} points", score)),
+        Status::Disconnected => Some("Lost the server".to_string()),
+    };
+
+    let Some(message) = message else {
+        return;
+    };
+    overlays.push(move |frame, area| {
+        let area = crate::terminal::centered(area, 30, 4);
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(message)
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL)),
+            area,
+        );
+    });
+}
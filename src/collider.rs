@@ -1,6 +1,9 @@
 use bevy::math::f32::Vec2;
 use bevy::transform::components::GlobalTransform;
 
+/// Side length of the cells of the broadphase spatial hash.
+const CELL_SIZE: f32 = 8.0;
+
 #[derive(bevy::ecs::component::Component, Debug, Default, PartialEq)]
 pub struct Collider(Vec2);
 
@@ -23,27 +26,330 @@ impl std::ops::DerefMut for Collider {
     }
 }
 
+/// Opt in to cell accurate collisions.
+///
+/// Boxes which pass the AABB test only collide if at least one cell is
+/// occupied by both, where a collider with a mask occupies only the cells
+/// set in its mask and a collider without one occupies its whole box.
+#[derive(bevy::ecs::component::Component, Clone, Debug, PartialEq)]
+pub struct Mask(ndarray::Array2<bool>);
+
+impl Mask {
+    /// A mask of the non-empty cells of a sprite.
+    pub fn from_sprite(sprite: &crate::sprite::Sprite) -> Self {
+        Self(sprite.buffer.0.map(|cell| cell.character.is_some()))
+    }
+}
+
+/// Opt in to continuous collision detection.
+///
+/// The collider is tested along the whole path it travelled since the
+/// previous tick, so fast colliders cannot tunnel through thin ones.
+#[derive(bevy::ecs::component::Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sweep {
+    previous: Option<Vec2>,
+}
+
+pub mod layer {
+    pub const PLAYER: u32 = 0b0001;
+    pub const ENEMY: u32 = 0b0010;
+    pub const SHOT: u32 = 0b0100;
+}
+
+/// Which collision layer an entity is on and which layers it collides with.
+///
+/// Only entities with a `Collider` and `CollisionLayers` take part in
+/// collision detection.
+#[derive(bevy::ecs::component::Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl CollisionLayers {
+    pub fn new(layer: u32, mask: u32) -> Self {
+        Self { layer, mask }
+    }
+
+    pub fn interacts(&self, other: &Self) -> bool {
+        self.mask & other.layer != 0 || other.mask & self.layer != 0
+    }
+}
+
+/// Sent once per tick for each pair of overlapping entities.
+#[derive(bevy::ecs::event::Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: bevy::ecs::entity::Entity,
+    pub b: bevy::ecs::entity::Entity,
+}
+
+impl CollisionEvent {
+    /// The two entities ordered such that the first satisfies `first` and
+    /// the second satisfies `second`, if they can be.
+    pub fn matching(
+        &self,
+        first: impl Fn(bevy::ecs::entity::Entity) -> bool,
+        second: impl Fn(bevy::ecs::entity::Entity) -> bool,
+    ) -> Option<(bevy::ecs::entity::Entity, bevy::ecs::entity::Entity)> {
+        if first(self.a) && second(self.b) {
+            Some((self.a, self.b))
+        } else if first(self.b) && second(self.a) {
+            Some((self.b, self.a))
+        } else {
+            None
+        }
+    }
+}
+
+/// Systems which consume `CollisionEvent`s should run after this set.
+#[derive(bevy::ecs::schedule::SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionDetection;
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.add_event::<CollisionEvent>();
+    app.add_systems(
+        bevy::app::Update,
+        detect_collisions.in_set(CollisionDetection),
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn new(collider: &Collider, transform: &GlobalTransform) -> Self {
+        use bevy::math::Vec3Swizzles;
+        let min = transform.translation().xy();
+        Self {
+            min,
+            max: min + **collider,
+        }
+    }
+
+    pub fn translated(&self, offset: Vec2) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Whether this box hits `target` at any point while moving by `motion`.
+    pub fn sweep_intersects(&self, motion: Vec2, target: &Self) -> bool {
+        // cast the min corner against the target grown by this box's size
+        let size = self.max - self.min;
+        let (lo, hi) = (target.min - size, target.max);
+        let (mut t_min, mut t_max) = (0.0f32, 1.0f32);
+        for axis in 0..2 {
+            let (origin, delta) = (self.min[axis], motion[axis]);
+            if delta.abs() <= f32::EPSILON {
+                if origin < lo[axis] || origin > hi[axis] {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (lo[axis] - origin) / delta;
+            let t1 = (hi[axis] - origin) / delta;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_min > t_max {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Boxes intersect if they overlap or share an edge.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
 #[allow(dead_code)]
 pub fn collide(c1: (&Collider, &GlobalTransform), c2: (&Collider, &GlobalTransform)) -> bool {
-    use bevy::math::Vec3Swizzles;
+    Aabb::new(c1.0, c1.1).intersects(&Aabb::new(c2.0, c2.1))
+}
 
-    let top_left1 = c1.1.translation().xy();
-    let bottom_right1 = c1.1.translation().xy() + **c1.0;
+/// The terminal cells covered by a collider, positioned the way sprites are
+/// rendered.
+struct Cells<'a> {
+    origin: bevy::math::IVec2,
+    size: bevy::math::IVec2,
+    mask: Option<&'a Mask>,
+}
 
-    let top_left2 = c2.1.translation().xy();
-    let bottom_right2 = c2.1.translation().xy() + **c2.0;
+impl<'a> Cells<'a> {
+    fn new(aabb: &Aabb, mask: Option<&'a Mask>) -> Self {
+        let size = match mask {
+            Some(mask) => bevy::math::IVec2::new(mask.0.ncols() as i32, mask.0.nrows() as i32),
+            None => (aabb.max - aabb.min).ceil().as_ivec2(),
+        };
+        Self {
+            origin: aabb.min.round().as_ivec2(),
+            size,
+            mask,
+        }
+    }
 
-    let top_left1_inside_2 = top_left1.x > top_left2.x
-        && top_left1.x < bottom_right2.x
-        && top_left1.y > top_left2.y
-        && top_left1.y < bottom_right2.y;
+    fn occupies(&self, cell: bevy::math::IVec2) -> bool {
+        let local = cell - self.origin;
+        if local.x < 0 || local.y < 0 || local.x >= self.size.x || local.y >= self.size.y {
+            return false;
+        }
+        match self.mask {
+            Some(mask) => mask.0[[local.y as usize, local.x as usize]],
+            None => true,
+        }
+    }
 
-    let bottom_right1_inside_2 = bottom_right1.x > top_left2.x
-        && bottom_right1.x < bottom_right2.x
-        && bottom_right1.y > top_left2.y
-        && bottom_right1.y < bottom_right2.y;
+    fn overlaps(&self, other: &Cells) -> bool {
+        use itertools::Itertools;
+        (0..self.size.y)
+            .cartesian_product(0..self.size.x)
+            .map(|(row, col)| self.origin + bevy::math::IVec2::new(col, row))
+            .any(|cell| self.occupies(cell) && other.occupies(cell))
+    }
+}
 
-    top_left1_inside_2 || bottom_right1_inside_2
+/// Narrowphase test for a pair of bodies which may have masks.
+fn bodies_collide(a: (&Aabb, Option<&Mask>), b: (&Aabb, Option<&Mask>)) -> bool {
+    if !a.0.intersects(b.0) {
+        return false;
+    }
+    if a.1.is_none() && b.1.is_none() {
+        return true;
+    }
+    Cells::new(a.0, a.1).overlaps(&Cells::new(b.0, b.1))
+}
+
+/// Narrowphase test for a pair of bodies which moved by the given motion
+/// since the previous tick, given their current boxes.
+fn sweeps_collide(a: (&Aabb, Option<&Mask>, Vec2), b: (&Aabb, Option<&Mask>, Vec2)) -> bool {
+    let a_start = a.0.translated(-a.2);
+    let b_start = b.0.translated(-b.2);
+    if a.1.is_none() && b.1.is_none() {
+        return a_start.sweep_intersects(a.2 - b.2, &b_start);
+    }
+
+    // masks can't be swept analytically so step through every cell of the path
+    let steps = (a.2 - b.2).abs().max_element().ceil().max(1.0);
+    (0..=steps as usize).any(|step| {
+        let t = step as f32 / steps;
+        bodies_collide(
+            (&a_start.translated(a.2 * t), a.1),
+            (&b_start.translated(b.2 * t), b.1),
+        )
+    })
+}
+
+/// Broadphase which buckets boxes into a uniform grid so only boxes which
+/// share a cell need to be tested against each other.
+struct SpatialHash {
+    cell_size: f32,
+    cells: std::collections::HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: Default::default(),
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn insert(&mut self, index: usize, aabb: &Aabb) {
+        let (min_x, min_y) = self.cell(aabb.min);
+        let (max_x, max_y) = self.cell(aabb.max);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Every pair of indices which share at least one cell, lowest first.
+    fn candidates(&self) -> Vec<(usize, usize)> {
+        let mut pairs = std::collections::HashSet::new();
+        for indices in self.cells.values() {
+            use itertools::Itertools;
+            for (&i, &j) in indices.iter().tuple_combinations() {
+                pairs.insert((i.min(j), i.max(j)));
+            }
+        }
+        let mut pairs: Vec<_> = pairs.into_iter().collect();
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn detect_collisions(
+    mut query: bevy::ecs::system::Query<(
+        bevy::ecs::entity::Entity,
+        &Collider,
+        &GlobalTransform,
+        &CollisionLayers,
+        Option<&Mask>,
+        Option<&mut Sweep>,
+    )>,
+    mut writer: bevy::ecs::event::EventWriter<CollisionEvent>,
+) {
+    let bodies: Vec<_> = query
+        .iter_mut()
+        .map(|(entity, collider, transform, layers, mask, sweep)| {
+            let aabb = Aabb::new(collider, transform);
+            let motion = match sweep {
+                Some(mut sweep) => {
+                    let previous = sweep.previous.replace(aabb.min).unwrap_or(aabb.min);
+                    aabb.min - previous
+                }
+                None => Vec2::ZERO,
+            };
+            (entity, aabb, *layers, mask, motion)
+        })
+        .collect();
+
+    let mut hash = SpatialHash::new(CELL_SIZE);
+    for (index, (_, aabb, _, _, motion)) in bodies.iter().enumerate() {
+        hash.insert(index, &aabb.union(&aabb.translated(-*motion)));
+    }
+
+    for (i, j) in hash.candidates() {
+        let (a, a_aabb, a_layers, a_mask, a_motion) = &bodies[i];
+        let (b, b_aabb, b_layers, b_mask, b_motion) = &bodies[j];
+        if !a_layers.interacts(b_layers) {
+            continue;
+        }
+        let collision = if *a_motion == Vec2::ZERO && *b_motion == Vec2::ZERO {
+            bodies_collide((a_aabb, *a_mask), (b_aabb, *b_mask))
+        } else {
+            sweeps_collide((a_aabb, *a_mask, *a_motion), (b_aabb, *b_mask, *b_motion))
+        };
+        if collision {
+            writer.send(CollisionEvent { a: *a, b: *b });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +357,11 @@ mod tests {
     use super::*;
     use bevy::math::f32::Vec3;
     use bevy::transform::components::Transform;
+    use pretty_assertions::assert_eq;
+
+    fn at(x: f32, y: f32) -> GlobalTransform {
+        Transform::from_translation(Vec3::new(x, y, 0.0)).into()
+    }
 
     #[test]
     fn no_collision() {
@@ -84,4 +395,172 @@ mod tests {
             ),
         ));
     }
+
+    #[test]
+    fn contained_collision() {
+        //  ________
+        // |  ___   |
+        // | |___|  |
+        // |________|
+        //
+        let outer = (&Collider::new(4.0, 4.0), &at(0.0, 0.0));
+        let inner = (&Collider::new(1.0, 1.0), &at(1.0, 1.0));
+        assert!(collide(outer, inner));
+        assert!(collide(inner, outer));
+    }
+
+    #[test]
+    fn crossing_collision() {
+        //     _
+        //  __| |__
+        // |__   __|
+        //    |_|
+        //
+        let wide = (&Collider::new(5.0, 1.0), &at(0.0, 2.0));
+        let tall = (&Collider::new(1.0, 5.0), &at(2.0, 0.0));
+        assert!(collide(wide, tall));
+        assert!(collide(tall, wide));
+    }
+
+    #[test]
+    fn touching_collision() {
+        //  _ _
+        // |_|_|
+        //
+        let left = (&Collider::new(1.0, 1.0), &at(0.0, 0.0));
+        let right = (&Collider::new(1.0, 1.0), &at(1.0, 0.0));
+        assert!(collide(left, right));
+        assert!(collide(right, left));
+    }
+
+    fn hollow() -> Mask {
+        // x x
+        //   x
+        // x x
+        Mask(ndarray::array![[true, true], [false, true], [true, true]])
+    }
+
+    #[test]
+    fn mask_ignores_empty_cells() {
+        let shape = Aabb::new(&Collider::new(2.0, 3.0), &at(0.0, 0.0));
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(0.0, 1.0));
+        assert!(shape.intersects(&shot));
+        assert!(!bodies_collide((&shape, Some(&hollow())), (&shot, None)));
+        assert!(!bodies_collide((&shot, None), (&shape, Some(&hollow()))));
+    }
+
+    #[test]
+    fn mask_collides_on_occupied_cells() {
+        let shape = Aabb::new(&Collider::new(2.0, 3.0), &at(0.0, 0.0));
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(1.0, 1.0));
+        assert!(bodies_collide((&shape, Some(&hollow())), (&shot, None)));
+    }
+
+    #[test]
+    fn masks_collide_where_cells_coincide() {
+        let left = Mask(ndarray::array![[true, false]]);
+        let right = Mask(ndarray::array![[false, true]]);
+        let a = Aabb::new(&Collider::new(2.0, 1.0), &at(0.0, 0.0));
+        let b = Aabb::new(&Collider::new(2.0, 1.0), &at(0.0, 0.0));
+        assert!(!bodies_collide((&a, Some(&left)), (&b, Some(&right))));
+        let shifted = Aabb::new(&Collider::new(2.0, 1.0), &at(-1.0, 0.0));
+        assert!(bodies_collide((&a, Some(&left)), (&shifted, Some(&right))));
+    }
+
+    #[test]
+    fn fast_shot_hits_thin_target() {
+        // moved 20 columns this tick, straight through a 1 column target
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(20.0, 3.0));
+        let target = Aabb::new(&Collider::new(1.0, 5.0), &at(10.0, 0.0));
+        let motion = Vec2::new(20.0, 0.0);
+        assert!(!bodies_collide((&shot, None), (&target, None)));
+        assert!(sweeps_collide(
+            (&shot, None, motion),
+            (&target, None, Vec2::ZERO)
+        ));
+    }
+
+    #[test]
+    fn fast_shot_misses_target_off_its_path() {
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(20.0, 7.0));
+        let target = Aabb::new(&Collider::new(1.0, 5.0), &at(10.0, 0.0));
+        let motion = Vec2::new(20.0, 0.0);
+        assert!(!sweeps_collide(
+            (&shot, None, motion),
+            (&target, None, Vec2::ZERO)
+        ));
+    }
+
+    #[test]
+    fn fast_shot_stops_short_of_target() {
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(8.0, 3.0));
+        let target = Aabb::new(&Collider::new(1.0, 5.0), &at(10.0, 0.0));
+        let motion = Vec2::new(8.0, 0.0);
+        assert!(!sweeps_collide(
+            (&shot, None, motion),
+            (&target, None, Vec2::ZERO)
+        ));
+    }
+
+    #[test]
+    fn fast_shot_hits_target_moving_towards_it() {
+        // closing speed is what matters: neither box alone crosses the other
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(12.0, 3.0));
+        let target = Aabb::new(&Collider::new(1.0, 5.0), &at(8.0, 0.0));
+        assert!(!bodies_collide((&shot, None), (&target, None)));
+        assert!(sweeps_collide(
+            (&shot, None, Vec2::new(12.0, 0.0)),
+            (&target, None, Vec2::new(-4.0, 0.0))
+        ));
+    }
+
+    #[test]
+    fn fast_shot_hits_thin_masked_target() {
+        // the empty cell of the hollow mask is at row 1, column 0 but the
+        // shot still crosses column 1 on its way through
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(30.0, 1.0));
+        let target = Aabb::new(&Collider::new(2.0, 3.0), &at(10.0, 0.0));
+        let motion = Vec2::new(30.0, 0.0);
+        assert!(sweeps_collide(
+            (&shot, None, motion),
+            (&target, Some(&hollow()), Vec2::ZERO)
+        ));
+    }
+
+    #[test]
+    fn fast_shot_passes_through_masked_gap() {
+        let gap = Mask(ndarray::array![[true], [false], [true]]);
+        let shot = Aabb::new(&Collider::new(1.0, 1.0), &at(30.0, 1.0));
+        let target = Aabb::new(&Collider::new(1.0, 3.0), &at(10.0, 0.0));
+        let motion = Vec2::new(30.0, 0.0);
+        assert!(!sweeps_collide(
+            (&shot, None, motion),
+            (&target, Some(&gap), Vec2::ZERO)
+        ));
+    }
+
+    #[test]
+    fn layers_interact_through_either_mask() {
+        let player = CollisionLayers::new(layer::PLAYER, layer::ENEMY);
+        let enemy = CollisionLayers::new(layer::ENEMY, 0);
+        let shot = CollisionLayers::new(layer::SHOT, layer::ENEMY);
+        assert!(player.interacts(&enemy));
+        assert!(enemy.interacts(&shot));
+        assert!(!player.interacts(&shot));
+    }
+
+    #[test]
+    fn spatial_hash_pairs_nearby_boxes_only() {
+        let mut hash = SpatialHash::new(CELL_SIZE);
+        let aabb = |x: f32, y: f32| Aabb {
+            min: Vec2::new(x, y),
+            max: Vec2::new(x + 1.0, y + 1.0),
+        };
+        hash.insert(0, &aabb(0.0, 0.0));
+        hash.insert(1, &aabb(2.0, 2.0));
+        hash.insert(2, &aabb(100.0, 100.0));
+        // straddles the boundary into the cell of box 2
+        hash.insert(3, &aabb(95.0, 95.0));
+        assert_eq!(hash.candidates(), vec![(0, 1), (2, 3)]);
+    }
 }
@@ -0,0 +1,200 @@
+/// User configuration, persisted as TOML in the config directory.
+#[derive(
+    bevy::ecs::system::Resource, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Config {
+    pub bindings: crate::input::Bindings,
+    /// Keys for the second ship in co-op.
+    pub player_two: crate::input::Bindings,
+    pub difficulty: crate::difficulty::Difficulty,
+    /// Ease off or push harder depending on how the player is doing.
+    pub adaptive_difficulty: bool,
+    pub colour: crate::terminal::ColourMode,
+    /// Frames drawn per second, at most.
+    pub frame_rate: u32,
+    /// Ring the terminal bell when the game ends.
+    pub sound: bool,
+}
+
+pub const DEFAULT_FRAME_RATE: u32 = 60;
+pub const MIN_FRAME_RATE: u32 = 10;
+pub const MAX_FRAME_RATE: u32 = 240;
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bindings: Default::default(),
+            player_two: crate::input::Bindings::player_two(),
+            difficulty: Default::default(),
+            adaptive_difficulty: false,
+            colour: Default::default(),
+            frame_rate: DEFAULT_FRAME_RATE,
+            sound: true,
+        }
+    }
+}
+
+/// Whether changes to the config are saved to the file, which a game
+/// hosted for someone else doesn't do.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Persist(pub bool);
+
+impl Default for Persist {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    app.insert_resource(Config::load());
+    app.init_resource::<Persist>();
+}
+
+impl Config {
+    /// The player and action `code` is bound to, when `players` are
+    /// playing. The second player's keys win over the first's in co-op.
+    pub fn action(
+        &self,
+        code: crossterm::event::KeyCode,
+        players: usize,
+    ) -> Option<(usize, crate::input::Action)> {
+        let player_two = (players > 1)
+            .then(|| self.player_two.action(code))
+            .flatten();
+        match player_two {
+            Some(action) => Some((1, action)),
+            None => self.bindings.action(code).map(|action| (0, action)),
+        }
+    }
+
+    pub fn path() -> Option<std::path::PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("shellaga").join("config.toml"))
+    }
+
+    /// Load the config file, falling back to the defaults for anything
+    /// missing or if the file can't be read. A file which can't be parsed is
+    /// moved aside, so saving the defaults doesn't lose what was in it.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            log::warn!("No config directory, using default config");
+            return Self::default();
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::error!("Couldn't read config {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+
+        match Self::from_toml(&contents) {
+            Ok(config) => {
+                log::info!("Loaded config from {}", path.display());
+                config
+            }
+            Err(e) => {
+                let mut backup = path.clone().into_os_string();
+                backup.push(".corrupt");
+                log::error!(
+                    "Couldn't parse config {}, moving it to {}: {}",
+                    path.display(),
+                    std::path::Path::new(&backup).display(),
+                    e
+                );
+                if let Err(e) = std::fs::rename(&path, &backup) {
+                    log::error!("Couldn't move config aside: {}", e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path().ok_or("no config directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)?;
+        log::info!("Saved config to {}", path.display());
+        Ok(())
+    }
+
+    fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        let mut config: Self = toml::from_str(contents)?;
+        let mut bindings = crate::input::Bindings::default();
+        bindings.merge(config.bindings);
+        config.bindings = bindings;
+        let mut player_two = crate::input::Bindings::player_two();
+        player_two.merge(config.player_two);
+        config.player_two = player_two;
+        config.frame_rate = config.frame_rate.clamp(MIN_FRAME_RATE, MAX_FRAME_RATE);
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn empty_config_is_default() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn bindings_override_defaults() {
+        use crate::input::{Action, Key};
+        use crossterm::event::KeyCode::*;
+        let config = Config::from_toml("[bindings]\nquit = [\"q\"]\n").unwrap();
+        assert_eq!(config.bindings.keys(Action::Quit), &[Key(Char('q'))]);
+        assert_eq!(config.bindings.action(Char(' ')), Some(Action::Fire));
+    }
+
+    #[test]
+    fn config_round_trips() {
+        let mut config = Config {
+            colour: crate::terminal::ColourMode::Monochrome,
+            frame_rate: 30,
+            ..Default::default()
+        };
+        config.bindings.bind(
+            crate::input::Action::Bomb,
+            crate::input::Key(crossterm::event::KeyCode::Tab),
+        );
+        let toml = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(Config::from_toml(&toml).unwrap(), config);
+    }
+
+    #[test]
+    fn settings_override_defaults() {
+        let config = Config::from_toml(
+            "difficulty = \"hard\"\ncolour = \"monochrome\"\nframe_rate = 1000\nsound = false\n",
+        )
+        .unwrap();
+        assert_eq!(config.difficulty, crate::difficulty::Difficulty::Hard);
+        assert_eq!(config.colour, crate::terminal::ColourMode::Monochrome);
+        assert_eq!(config.frame_rate, MAX_FRAME_RATE);
+        assert!(!config.sound);
+    }
+
+    #[test]
+    fn second_player_keys_only_in_co_op() {
+        use crate::input::Action;
+        use crossterm::event::KeyCode::*;
+        let config = Config::from_toml("[player_two]\nfire = [\"0\"]\n").unwrap();
+        assert_eq!(config.action(Up, 1), Some((0, Action::MoveUp)));
+        assert_eq!(config.action(Up, 2), Some((1, Action::MoveUp)));
+        assert_eq!(config.action(Char('0'), 2), Some((1, Action::Fire)));
+        assert_eq!(config.action(Enter, 2), None);
+        assert_eq!(config.action(Char('w'), 2), Some((0, Action::MoveUp)));
+    }
+
+    #[test]
+    fn bad_keys_are_errors() {
+        assert!(Config::from_toml("[bindings]\nfire = [\"Hyper\"]\n").is_err());
+    }
+}
@@ -0,0 +1,501 @@
+use crate::input::{Action, Bindings};
+
+/// Lines of output kept for scrolling back through.
+const SCROLLBACK: usize = 100;
+/// Rows the console takes from the top of the play area.
+const HEIGHT: u16 = 10;
+
+const COMMANDS: [(&str, &str); 7] = [
+    ("help", "list commands"),
+    ("spawn", "spawn enemy <x> <y>, relative to the frame"),
+    ("god", "god on|off, enemies pass through the player"),
+    ("weapon", "weapon single|spread"),
+    ("speed", "speed <factor>, how fast game time passes"),
+    ("goto", "goto <percent>% or goto <distance>"),
+    ("seed", "seed <number>, for enemies generated from now on"),
+];
+
+/// Where to move the frame to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goto {
+    /// Percentage of the way through a fixed length level.
+    Percent(f32),
+    Distance(f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    SpawnEnemy { x: f32, y: f32 },
+    God(bool),
+    Weapon(crate::weapon::Pattern),
+    Speed(f32),
+    Goto(Goto),
+    Seed(u64),
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn number<T: std::str::FromStr>(word: Option<&str>, name: &str) -> Result<T, String> {
+            let word = word.ok_or_else(|| format!("missing {}", name))?;
+            word.parse()
+                .map_err(|_| format!("{} \"{}\" isn't a number", name, word))
+        }
+
+        let mut words = s.split_whitespace();
+        let command = match words.next() {
+            Some("help") => Command::Help,
+            Some("spawn") => match words.next() {
+                Some("enemy") => Command::SpawnEnemy {
+                    x: number(words.next(), "x")?,
+                    y: number(words.next(), "y")?,
+                },
+                Some(other) => return Err(format!("can't spawn \"{}\"", other)),
+                None => return Err("spawn what?".to_string()),
+            },
+            Some("god") => match words.next() {
+                Some("on") => Command::God(true),
+                Some("off") => Command::God(false),
+                _ => return Err("god on or god off".to_string()),
+            },
+            Some("weapon") => Command::Weapon(words.next().unwrap_or_default().parse()?),
+            Some("speed") => {
+                let factor: f32 = number(words.next(), "speed")?;
+                if !(factor >= 0.0 && factor.is_finite()) {
+                    return Err("speed can't be negative".to_string());
+                }
+                Command::Speed(factor)
+            }
+            Some("goto") => match words.next() {
+                Some(word) => match word.strip_suffix('%') {
+                    Some(percent) => {
+                        Command::Goto(Goto::Percent(number(Some(percent), "percent")?))
+                    }
+                    None => Command::Goto(Goto::Distance(number(Some(word), "distance")?)),
+                },
+                None => return Err("missing distance".to_string()),
+            },
+            Some("seed") => Command::Seed(number(words.next(), "seed")?),
+            Some(other) => return Err(format!("unknown command \"{}\", try help", other)),
+            None => return Err("empty command".to_string()),
+        };
+        match words.next() {
+            Some(extra) => Err(format!("unexpected \"{}\"", extra)),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Words which can follow `command`, for tab completion.
+fn arguments(command: &str) -> Vec<&'static str> {
+    match command {
+        "spawn" => vec!["enemy"],
+        "god" => vec!["on", "off"],
+        "weapon" => crate::weapon::Pattern::ALL
+            .iter()
+            .map(crate::weapon::Pattern::name)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The longest start every candidate shares.
+fn common_prefix<'a>(candidates: &[&'a str]) -> &'a str {
+    let Some((first, rest)) = candidates.split_first() else {
+        return "";
+    };
+    rest.iter().fold(first, |common, candidate| {
+        // in bytes, so slicing can't land inside a character
+        let length = common
+            .chars()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        &common[..length]
+    })
+}
+
+/// Drop-down console for developers to poke at the running game.
+///
+/// Like the bindings screen it pauses the game and receives every key
+/// event while it's open. Submitted lines wait in `pending` until
+/// `execute` runs them.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Console {
+    open: bool,
+    was_paused: bool,
+    input: String,
+    output: Vec<String>,
+    history: Vec<String>,
+    /// Index into `history` while browsing it.
+    browsing: Option<usize>,
+    pending: Vec<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, time: &mut bevy::time::Time<bevy::time::Virtual>) {
+        log::info!("Opening console");
+        self.open = true;
+        self.was_paused = time.is_paused();
+        time.pause();
+    }
+
+    pub fn close(&mut self, time: &mut bevy::time::Time<bevy::time::Virtual>) {
+        log::info!("Closing console");
+        self.open = false;
+        if !self.was_paused {
+            time.unpause();
+        }
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > SCROLLBACK {
+            self.output.drain(..self.output.len() - SCROLLBACK);
+        }
+    }
+
+    /// Returns false once the console wants to close.
+    pub fn handle_key(&mut self, key: &crossterm::event::KeyEvent, bindings: &Bindings) -> bool {
+        use crossterm::event::KeyCode::*;
+
+        if key.kind == crossterm::event::KeyEventKind::Release {
+            return true;
+        }
+
+        match key.code {
+            Esc => return false,
+            code if bindings.action(code) == Some(Action::Console) => return false,
+            Enter => self.submit(),
+            Tab => self.complete(),
+            Up => self.browse_back(),
+            Down => self.browse_forward(),
+            Backspace => {
+                self.input.pop();
+            }
+            Char(c)
+                if !key.modifiers.intersects(
+                    crossterm::event::KeyModifiers::CONTROL | crossterm::event::KeyModifiers::ALT,
+                ) =>
+            {
+                self.input.push(c)
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.browsing = None;
+        self.print(format!("> {}", line));
+        let line = line.trim().to_string();
+        if line.is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.pending.push(line);
+    }
+
+    fn browse_back(&mut self) {
+        let index = match self.browsing {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.browsing = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    fn browse_forward(&mut self) {
+        let Some(index) = self.browsing else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.browsing = Some(index + 1);
+            self.input = self.history[index + 1].clone();
+        } else {
+            self.browsing = None;
+            self.input.clear();
+        }
+    }
+
+    /// Complete the last word, or list the candidates if it's ambiguous.
+    fn complete(&mut self) {
+        let split = self.input.rfind(' ').map_or(0, |i| i + 1);
+        let (head, partial) = self.input.split_at(split);
+        let candidates = match head.split_whitespace().collect::<Vec<_>>()[..] {
+            [] => COMMANDS.iter().map(|(name, _)| *name).collect(),
+            [command] => arguments(command),
+            _ => Vec::new(),
+        };
+        let matches: Vec<_> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(partial))
+            .collect();
+
+        match matches[..] {
+            [] => {}
+            [only] => self.input = format!("{}{} ", head, only),
+            _ => {
+                let common = common_prefix(&matches);
+                if common.len() > partial.len() {
+                    self.input = format!("{}{}", head, common);
+                } else {
+                    let listing = matches.join("  ");
+                    self.print(listing);
+                }
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<Console>();
+    app.add_systems(bevy::app::Update, execute);
+    app.add_systems(bevy::app::PostUpdate, render.run_if(is_open));
+}
+
+fn is_open(console: bevy::ecs::system::Res<Console>) -> bool {
+    console.open
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute(
+    mut commands: bevy::ecs::system::Commands,
+    mut console: bevy::ecs::system::ResMut<Console>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
+    mut rng: bevy::ecs::system::ResMut<crate::level::Rng>,
+    level_query: bevy::ecs::system::Query<(bevy::ecs::entity::Entity, &crate::level::Level)>,
+    mut frame_query: bevy::ecs::system::Query<
+        (
+            &mut bevy::transform::components::Transform,
+            &crate::collider::Collider,
+        ),
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+    player_query: bevy::ecs::system::Query<
+        bevy::ecs::entity::Entity,
+        bevy::ecs::query::With<crate::player::PlayerState>,
+    >,
+    mut weapon_query: bevy::ecs::system::Query<&mut crate::weapon::Weapon>,
+) {
+    let pending = std::mem::take(&mut console.pending);
+    for line in pending {
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(e) => {
+                console.print(e);
+                continue;
+            }
+        };
+        log::info!("Console command {:?}", command);
+
+        match command {
+            Command::Help => {
+                for (name, usage) in COMMANDS {
+                    console.print(format!("{:<8}{}", name, usage));
+                }
+            }
+            Command::SpawnEnemy { x, y } => {
+                let (Ok((level, _)), Ok((frame_transform, _))) =
+                    (level_query.get_single(), frame_query.get_single())
+                else {
+                    console.print("no level to spawn into");
+                    continue;
+                };
+                use bevy::hierarchy::BuildChildren;
+                let position = frame_transform.translation + bevy::math::f32::Vec3::new(x, y, 0.0);
+                commands
+                    .spawn(crate::enemy::Enemy::bundle())
+                    .insert(bevy::transform::TransformBundle::from_transform(
+                        bevy::transform::components::Transform::from_translation(position),
+                    ))
+                    .set_parent(level);
+                console.print(format!("spawned enemy at {}, {}", x, y));
+            }
+            Command::God(on) => {
+                for player in &player_query {
+                    if on {
+                        commands.entity(player).insert(crate::player::Invulnerable);
+                    } else {
+                        commands
+                            .entity(player)
+                            .remove::<crate::player::Invulnerable>();
+                    }
+                }
+                console.print(format!("god mode {}", if on { "on" } else { "off" }));
+            }
+            Command::Weapon(pattern) => {
+                for mut weapon in &mut weapon_query {
+                    weapon.pattern = pattern;
+                }
+                console.print(format!("weapon {}", pattern.name()));
+            }
+            Command::Speed(factor) => {
+                time.set_relative_speed(factor);
+                console.print(format!("speed {}", factor));
+            }
+            Command::Goto(goto) => {
+                let (Ok((_, level)), Ok((mut frame_transform, frame_collider))) =
+                    (level_query.get_single(), frame_query.get_single_mut())
+                else {
+                    console.print("no level to move through");
+                    continue;
+                };
+                // matches the progress logged by the frame
+                let x = match goto {
+                    Goto::Percent(_) if !level.length.is_finite() => {
+                        console.print("an endless level has no end to go a percent of");
+                        continue;
+                    }
+                    Goto::Percent(percent) => percent / 100.0 * (level.length - frame_collider.x),
+                    Goto::Distance(distance) => distance,
+                };
+                frame_transform.translation.x = x.max(0.0);
+                console.print(format!("frame at {:.0}", frame_transform.translation.x));
+            }
+            Command::Seed(seed) => {
+                use rand::SeedableRng;
+                rng.0 = rand::rngs::StdRng::seed_from_u64(seed);
+                console.print(format!("seeded with {}", seed));
+            }
+        }
+    }
+}
+
+fn render(
+    console: bevy::ecs::system::Res<Console>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    let output = console.output.clone();
+    let prompt = format!("> {}_", console.input);
+
+    overlays.push(move |frame, area| {
+        let area = ratatui::layout::Rect {
+            height: HEIGHT.min(area.height),
+            ..area
+        };
+        let rows = area.height.saturating_sub(2) as usize;
+        let mut lines: Vec<_> = output
+            .iter()
+            .skip((output.len() + 1).saturating_sub(rows))
+            .map(|line| ratatui::text::Line::raw(line.as_str()))
+            .collect();
+        lines.push(ratatui::text::Line::raw(prompt));
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(lines).block(
+                ratatui::widgets::Block::default()
+                    .title(" Console ")
+                    .borders(ratatui::widgets::Borders::ALL),
+            ),
+            area,
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn type_keys(console: &mut Console, text: &str) {
+        let bindings = Bindings::default();
+        for c in text.chars() {
+            let code = match c {
+                '\t' => crossterm::event::KeyCode::Tab,
+                '\n' => crossterm::event::KeyCode::Enter,
+                '↑' => crossterm::event::KeyCode::Up,
+                '↓' => crossterm::event::KeyCode::Down,
+                c => crossterm::event::KeyCode::Char(c),
+            };
+            let key = crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+            assert!(console.handle_key(&key, &bindings));
+        }
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            "spawn enemy 40 10".parse(),
+            Ok(Command::SpawnEnemy { x: 40.0, y: 10.0 })
+        );
+        assert_eq!(" god  on ".parse(), Ok(Command::God(true)));
+        assert_eq!(
+            "weapon spread".parse(),
+            Ok(Command::Weapon(crate::weapon::Pattern::Spread))
+        );
+        assert_eq!("speed 2.0".parse(), Ok(Command::Speed(2.0)));
+        assert_eq!("goto 80%".parse(), Ok(Command::Goto(Goto::Percent(80.0))));
+        assert_eq!("goto 500".parse(), Ok(Command::Goto(Goto::Distance(500.0))));
+        assert_eq!("seed 1234".parse(), Ok(Command::Seed(1234)));
+
+        assert_eq!(
+            "spawn enemy 40".parse::<Command>(),
+            Err("missing y".to_string())
+        );
+        assert_eq!(
+            "speed -1".parse::<Command>(),
+            Err("speed can't be negative".to_string())
+        );
+        assert_eq!(
+            "god on now".parse::<Command>(),
+            Err("unexpected \"now\"".to_string())
+        );
+        assert!("fly".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn tab_completion() {
+        let mut console = Console::default();
+        type_keys(&mut console, "sp\t");
+        assert_eq!(console.input, "sp");
+        assert_eq!(console.output, vec!["spawn  speed"]);
+
+        type_keys(&mut console, "a\t");
+        assert_eq!(console.input, "spawn ");
+        type_keys(&mut console, "\t");
+        assert_eq!(console.input, "spawn enemy ");
+
+        console.input = "weapon s".to_string();
+        type_keys(&mut console, "\t");
+        assert_eq!(console.input, "weapon s");
+        type_keys(&mut console, "p\t");
+        assert_eq!(console.input, "weapon spread ");
+    }
+
+    #[test]
+    fn common_prefix_keeps_whole_characters() {
+        assert_eq!(common_prefix(&["spawn", "speed"]), "sp");
+        assert_eq!(common_prefix(&["café", "cafè"]), "caf");
+        assert_eq!(common_prefix(&["né", "né!"]), "né");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn history_recalls_submitted_lines() {
+        let mut console = Console::default();
+        type_keys(&mut console, "god on\nspeed 2\nspeed 2\n");
+        assert_eq!(console.pending, vec!["god on", "speed 2", "speed 2"]);
+        assert_eq!(console.history, vec!["god on", "speed 2"]);
+
+        type_keys(&mut console, "↑↑↑");
+        assert_eq!(console.input, "god on");
+        type_keys(&mut console, "↓");
+        assert_eq!(console.input, "speed 2");
+        type_keys(&mut console, "↓");
+        assert_eq!(console.input, "");
+    }
+}
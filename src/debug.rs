@@ -0,0 +1,234 @@
+use bevy::math::Vec2;
+
+/// Weight of the newest frame in the smoothed frame time.
+const SMOOTHING: f32 = 0.1;
+
+/// What the debug overlay shows, cycled with the debug key.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugOverlay {
+    #[default]
+    Off,
+    Stats,
+    /// Stats plus an outline around every collider.
+    Colliders,
+}
+
+impl DebugOverlay {
+    pub fn next(self) -> Self {
+        match self {
+            DebugOverlay::Off => DebugOverlay::Stats,
+            DebugOverlay::Stats => DebugOverlay::Colliders,
+            DebugOverlay::Colliders => DebugOverlay::Off,
+        }
+    }
+}
+
+/// Smoothed real time between frames, kept up to date even while the
+/// overlay is hidden so it's settled by the time it's shown.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTime {
+    seconds: Option<f32>,
+}
+
+impl FrameTime {
+    pub fn update(&mut self, delta: f32) {
+        self.seconds = Some(match self.seconds {
+            Some(seconds) => seconds + (delta - seconds) * SMOOTHING,
+            None => delta,
+        });
+    }
+
+    pub fn fps(&self) -> f32 {
+        match self.seconds {
+            Some(seconds) if seconds > 0.0 => 1.0 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    pub fn millis(&self) -> f32 {
+        self.seconds.unwrap_or_default() * 1000.0
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<DebugOverlay>();
+    app.init_resource::<FrameTime>();
+    app.add_systems(bevy::app::First, measure);
+    app.add_systems(bevy::app::Update, toggle);
+    app.add_systems(bevy::app::PostUpdate, render.run_if(is_open));
+}
+
+fn is_open(overlay: bevy::ecs::system::Res<DebugOverlay>) -> bool {
+    *overlay != DebugOverlay::Off
+}
+
+fn measure(
+    time: bevy::ecs::system::Res<bevy::time::Time<bevy::time::Real>>,
+    mut frame_time: bevy::ecs::system::ResMut<FrameTime>,
+) {
+    frame_time.update(time.delta_seconds());
+}
+
+fn toggle(
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut overlay: bevy::ecs::system::ResMut<DebugOverlay>,
+) {
+    for event in reader.read() {
+        if event.action == crate::input::Action::Debug && event.pressed {
+            *overlay = overlay.next();
+            log::info!("Debug overlay {:?}", *overlay);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render(
+    overlay: bevy::ecs::system::Res<DebugOverlay>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+    frame_time: bevy::ecs::system::Res<FrameTime>,
+    metrics: bevy::ecs::system::Res<crate::terminal::Metrics>,
+    entities: bevy::ecs::system::Query<bevy::ecs::entity::Entity>,
+    enemies: bevy::ecs::system::Query<(), bevy::ecs::query::With<crate::enemy::Enemy>>,
+    shots: bevy::ecs::system::Query<(), bevy::ecs::query::With<crate::weapon::Shot>>,
+    sprites: bevy::ecs::system::Query<(), bevy::ecs::query::With<crate::sprite::Sprite>>,
+    colliders: bevy::ecs::system::Query<
+        (
+            &crate::collider::Collider,
+            &bevy::transform::components::GlobalTransform,
+            Option<&crate::collider::CollisionLayers>,
+        ),
+        bevy::ecs::query::Without<crate::frame::Frame>,
+    >,
+    frame_query: bevy::ecs::system::Query<
+        &bevy::transform::components::GlobalTransform,
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    use bevy::math::Vec3Swizzles;
+
+    let frame_position = frame_query
+        .get_single()
+        .map(|transform| transform.translation().xy())
+        .ok();
+    let lines = [
+        format!("FPS      {:.1}", frame_time.fps()),
+        format!("Frame    {:.1} ms", frame_time.millis()),
+        format!("Drawn    {} B", metrics.bytes_last_frame),
+        format!("Entities {}", entities.iter().count()),
+        format!("Enemies  {}", enemies.iter().count()),
+        format!("Shots    {}", shots.iter().count()),
+        format!("Sprites  {}", sprites.iter().count()),
+        match frame_position {
+            Some(position) => format!("View     {:.1}, {:.1}", position.x, position.y),
+            None => "View     none".to_string(),
+        },
+    ];
+
+    let outlines: Vec<_> = match (*overlay, frame_position) {
+        (DebugOverlay::Colliders, Some(frame_position)) => colliders
+            .iter()
+            .map(|(collider, transform, layers)| {
+                (
+                    transform.translation().xy() - frame_position,
+                    **collider,
+                    colour(layers),
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    overlays.push(move |frame, area| {
+        for (min, size, colour) in outlines {
+            for (x, y) in outline(min, size, area) {
+                frame.buffer_mut().get_mut(x, y).set_bg(colour);
+            }
+        }
+
+        let width = lines.iter().map(String::len).max().unwrap_or(0) as u16 + 2;
+        let panel = ratatui::layout::Rect::new(area.x, area.y, width, lines.len() as u16 + 2)
+            .intersection(area);
+        frame.render_widget(ratatui::widgets::Clear, panel);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(lines.join("\n")).block(
+                ratatui::widgets::Block::default()
+                    .title(" Debug ")
+                    .borders(ratatui::widgets::Borders::ALL),
+            ),
+            panel,
+        );
+    });
+}
+
+fn colour(layers: Option<&crate::collider::CollisionLayers>) -> ratatui::style::Color {
+    use crate::collider::layer;
+    match layers.map(|layers| layers.layer) {
+        Some(layer::PLAYER) => ratatui::style::Color::Green,
+        Some(layer::ENEMY) => ratatui::style::Color::Red,
+        Some(layer::SHOT) => ratatui::style::Color::Yellow,
+        _ => ratatui::style::Color::Cyan,
+    }
+}
+
+/// The cells of `area` on the edge of a box at `min` in frame space,
+/// rounded the same way sprites are.
+fn outline(min: Vec2, size: Vec2, area: ratatui::layout::Rect) -> Vec<(u16, u16)> {
+    let left = min.x.round() as i32;
+    let top = min.y.round() as i32;
+    let right = left + (size.x.ceil() as i32).max(1) - 1;
+    let bottom = top + (size.y.ceil() as i32).max(1) - 1;
+    let mut cells = Vec::new();
+    for y in top..=bottom {
+        for x in left..=right {
+            if x != left && x != right && y != top && y != bottom {
+                continue;
+            }
+            if x < 0 || y < 0 || x >= area.width as i32 || y >= area.height as i32 {
+                continue;
+            }
+            cells.push((area.x + x as u16, area.y + y as u16));
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn outline_edges_clipped_to_area() {
+        let area = ratatui::layout::Rect::new(10, 5, 4, 4);
+        assert_eq!(
+            outline(Vec2::new(1.0, 1.0), Vec2::new(3.0, 3.0), area),
+            vec![
+                (11, 6),
+                (12, 6),
+                (13, 6),
+                (11, 7),
+                (13, 7),
+                (11, 8),
+                (12, 8),
+                (13, 8)
+            ]
+        );
+        // hanging off the top left corner
+        assert_eq!(
+            outline(Vec2::new(-1.0, -1.0), Vec2::new(2.0, 2.0), area),
+            vec![(10, 5)]
+        );
+    }
+
+    #[test]
+    fn frame_time_is_smoothed() {
+        let mut frame_time = FrameTime::default();
+        assert_eq!(frame_time.fps(), 0.0);
+        frame_time.update(0.02);
+        assert_eq!(frame_time.fps(), 50.0);
+        frame_time.update(0.12);
+        assert_eq!(frame_time.millis().round(), 30.0);
+    }
+}
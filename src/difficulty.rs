@@ -0,0 +1,202 @@
+/// Accuracy the adaptive difficulty steers towards.
+const TARGET_ACCURACY: f32 = 0.3;
+/// Shots fired between each check of the player's accuracy.
+const ACCURACY_WINDOW: u32 = 20;
+/// How far each point of accuracy off target moves the adjustment.
+const ACCURACY_STEP: f32 = 0.5;
+/// How much easier the game gets each time the player dies.
+const DEATH_STEP: f32 = 0.15;
+const MIN_ADJUSTMENT: f32 = 0.5;
+const MAX_ADJUSTMENT: f32 = 1.5;
+
+/// How hard the game plays, picked in the settings menu.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane",
+        }
+    }
+
+    pub fn scaling(&self) -> Scaling {
+        match self {
+            Difficulty::Easy => Scaling {
+                enemy_density: 0.6,
+                enemy_speed: 0.7,
+                reload: 0.8,
+            },
+            Difficulty::Normal => Scaling::default(),
+            Difficulty::Hard => Scaling {
+                enemy_density: 1.5,
+                enemy_speed: 1.3,
+                reload: 1.2,
+            },
+            Difficulty::Insane => Scaling {
+                enemy_density: 2.5,
+                enemy_speed: 1.7,
+                reload: 1.5,
+            },
+        }
+    }
+}
+
+/// Multipliers applied to the level, the enemies and the player's weapon,
+/// kept up to date from the config and the player's performance.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Scaling {
+    /// How many enemies a level has.
+    pub enemy_density: f32,
+    /// How fast enemies fly at the player.
+    pub enemy_speed: f32,
+    /// How long the player's weapon takes to reload.
+    pub reload: f32,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self {
+            enemy_density: 1.0,
+            enemy_speed: 1.0,
+            reload: 1.0,
+        }
+    }
+}
+
+impl Scaling {
+    /// Everything made harder, or easier, by `factor`.
+    fn adjusted(self, factor: f32) -> Self {
+        Self {
+            enemy_density: self.enemy_density * factor,
+            enemy_speed: self.enemy_speed * factor,
+            reload: self.reload * factor,
+        }
+    }
+}
+
+/// How the player has been doing over the session, which the adaptive
+/// difficulty follows.
+#[derive(bevy::ecs::system::Resource, Clone, Debug, PartialEq)]
+pub struct Performance {
+    shots: u32,
+    hits: u32,
+    adjustment: f32,
+}
+
+impl Default for Performance {
+    fn default() -> Self {
+        Self {
+            shots: 0,
+            hits: 0,
+            adjustment: 1.0,
+        }
+    }
+}
+
+impl Performance {
+    /// Above one when the game should be harder than the preset, below
+    /// when it should be easier.
+    pub fn adjustment(&self) -> f32 {
+        self.adjustment
+    }
+
+    pub fn record_shot(&mut self) {
+        self.shots += 1;
+        if self.shots < ACCURACY_WINDOW {
+            return;
+        }
+        let accuracy = self.hits as f32 / self.shots as f32;
+        self.adjust((accuracy - TARGET_ACCURACY) * ACCURACY_STEP);
+        self.shots = 0;
+        self.hits = 0;
+    }
+
+    pub fn record_hit(&mut self) {
+        self.hits += 1;
+    }
+
+    pub fn record_death(&mut self) {
+        self.adjust(-DEATH_STEP);
+    }
+
+    fn adjust(&mut self, by: f32) {
+        self.adjustment = (self.adjustment + by).clamp(MIN_ADJUSTMENT, MAX_ADJUSTMENT);
+        log::info!("Difficulty adjusted to {:.2}", self.adjustment);
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    app.init_resource::<Scaling>();
+    app.init_resource::<Performance>();
+    app.add_systems(bevy::app::First, update_scaling);
+}
+
+fn update_scaling(
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    performance: bevy::ecs::system::Res<Performance>,
+    mut scaling: bevy::ecs::system::ResMut<Scaling>,
+) {
+    use bevy::ecs::change_detection::DetectChangesMut;
+
+    let preset = config.difficulty.scaling();
+    scaling.set_if_neq(if config.adaptive_difficulty {
+        preset.adjusted(performance.adjustment())
+    } else {
+        preset
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn presets_get_harder() {
+        for pair in Difficulty::ALL.windows(2) {
+            let (easier, harder) = (pair[0].scaling(), pair[1].scaling());
+            assert!(easier.enemy_density < harder.enemy_density);
+            assert!(easier.enemy_speed < harder.enemy_speed);
+            assert!(easier.reload < harder.reload);
+        }
+    }
+
+    #[test]
+    fn adjustment_follows_performance() {
+        let mut performance = Performance::default();
+        performance.record_death();
+        assert_eq!(performance.adjustment(), 1.0 - DEATH_STEP);
+
+        // every shot on target
+        for _ in 0..ACCURACY_WINDOW {
+            performance.record_hit();
+            performance.record_shot();
+        }
+        assert!(performance.adjustment() > 1.0);
+
+        for _ in 0..20 {
+            performance.record_death();
+        }
+        assert_eq!(performance.adjustment(), MIN_ADJUSTMENT);
+    }
+}
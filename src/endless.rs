@@ -0,0 +1,196 @@
+const CHUNK_LENGTH: f32 = 64.0;
+const ENEMY_SPACING: f32 = 6.0;
+const MIN_WAVES_PER_CHUNK: f32 = 1.0;
+const MAX_WAVES_PER_CHUNK: f32 = 6.0;
+const MAX_WAVE_SIZE: usize = 4;
+/// Distance over which the difficulty ramps most of the way to its maximum.
+const RAMP_DISTANCE: f32 = 3000.0;
+
+/// Marks a level which generates enemy waves ahead of the frame forever.
+#[derive(bevy::ecs::component::Component, Debug)]
+pub struct Endless {
+    generated_to: f32,
+}
+
+impl Endless {
+    pub fn new(frame_width: f32) -> Self {
+        Self {
+            // leave the opening screen clear, as the campaign does
+            generated_to: frame_width,
+        }
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::any_with_component, IntoSystemConfigs};
+
+    app.add_systems(
+        bevy::app::Update,
+        generate_chunks
+            .run_if(any_with_component::<Endless>)
+            .run_if(any_with_component::<crate::frame::Frame>),
+    );
+    app.add_systems(
+        bevy::app::Update,
+        despawn_behind_frame
+            .run_if(any_with_component::<Endless>)
+            .run_if(any_with_component::<crate::frame::Frame>)
+            .run_if(any_with_component::<crate::enemy::Enemy>),
+    );
+}
+
+/// Difficulty in the range 0..1 which ramps up with distance travelled.
+pub fn difficulty(distance: f32) -> f32 {
+    1.0 - (-distance.max(0.0) / RAMP_DISTANCE).exp()
+}
+
+/// Positions of the enemies in the chunk beginning at `start`, with
+/// `density` times as many waves as usual.
+fn plan_chunk(
+    start: f32,
+    height: f32,
+    density: f32,
+    rng: &mut impl rand::Rng,
+) -> Vec<bevy::math::f32::Vec2> {
+    let difficulty = difficulty(start);
+    let waves = ((MIN_WAVES_PER_CHUNK + difficulty * (MAX_WAVES_PER_CHUNK - MIN_WAVES_PER_CHUNK))
+        * density)
+        .round() as usize;
+    let max_wave_size = 1 + (difficulty * (MAX_WAVE_SIZE - 1) as f32).round() as usize;
+
+    let mut enemies = Vec::new();
+    for _ in 0..waves {
+        let size = rng.gen_range(1..=max_wave_size);
+        let x = start + rng.gen::<f32>() * CHUNK_LENGTH;
+        let wave_height = size as f32 * ENEMY_SPACING;
+        let top = rng.gen::<f32>() * (height - wave_height).max(0.0);
+        for i in 0..size {
+            enemies.push(bevy::math::f32::Vec2::new(
+                x,
+                top + i as f32 * ENEMY_SPACING,
+            ));
+        }
+    }
+    enemies
+}
+
+fn generate_chunks(
+    mut commands: bevy::ecs::system::Commands,
+    mut level_query: bevy::ecs::system::Query<(bevy::ecs::entity::Entity, &mut Endless)>,
+    mut rng: bevy::ecs::system::ResMut<crate::level::Rng>,
+    scaling: bevy::ecs::system::Res<crate::difficulty::Scaling>,
+    frame_query: bevy::ecs::system::Query<
+        (
+            &bevy::transform::components::GlobalTransform,
+            &crate::collider::Collider,
+        ),
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    let Ok((level, mut endless)) = level_query.get_single_mut() else {
+        log::error!("Couldn't get unique endless level instance");
+        return;
+    };
+
+    let Ok((frame_transform, frame_collider)) = frame_query.get_single() else {
+        log::error!("Couldn't get unique frame instance");
+        return;
+    };
+
+    let frame_right = frame_transform.translation().x + frame_collider.x;
+    use bevy::hierarchy::BuildChildren;
+    while endless.generated_to < frame_right + CHUNK_LENGTH {
+        let start = endless.generated_to;
+        log::debug!(
+            "Generating chunk at {:.0} with difficulty {:.2}",
+            start,
+            difficulty(start)
+        );
+        for position in plan_chunk(start, frame_collider.y, scaling.enemy_density, &mut rng.0) {
+            commands
+                .spawn(crate::enemy::Enemy::bundle())
+                .insert(bevy::transform::TransformBundle::from_transform(
+                    bevy::transform::components::Transform::from_translation(position.extend(0.0)),
+                ))
+                .set_parent(level);
+        }
+        endless.generated_to += CHUNK_LENGTH;
+    }
+}
+
+fn despawn_behind_frame(
+    mut commands: bevy::ecs::system::Commands,
+    enemy_query: bevy::ecs::system::Query<
+        (
+            bevy::ecs::entity::Entity,
+            &bevy::transform::components::GlobalTransform,
+            &crate::collider::Collider,
+        ),
+        bevy::ecs::query::With<crate::enemy::Enemy>,
+    >,
+    frame_query: bevy::ecs::system::Query<
+        &bevy::transform::components::GlobalTransform,
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    let Ok(frame_transform) = frame_query.get_single() else {
+        log::error!("Couldn't get unique frame instance");
+        return;
+    };
+
+    let frame_left = frame_transform.translation().x;
+    for (entity, transform, collider) in &enemy_query {
+        if transform.translation().x + collider.x < frame_left {
+            log::trace!("Despawning enemy behind frame");
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn difficulty_ramps_with_distance() {
+        assert_eq!(difficulty(0.0), 0.0);
+        assert!(difficulty(1000.0) < difficulty(2000.0));
+        assert!(difficulty(1_000_000.0) <= 1.0);
+    }
+
+    #[test]
+    fn chunk_enemies_stay_in_chunk() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+        for position in plan_chunk(640.0, 32.0, 1.0, &mut rng) {
+            assert!(position.x >= 640.0 && position.x <= 640.0 + CHUNK_LENGTH);
+            assert!(position.y >= 0.0 && position.y < 32.0);
+        }
+    }
+
+    #[test]
+    fn later_chunks_have_more_enemies() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+        let count = |start: f32, rng: &mut rand::rngs::StdRng| -> usize {
+            (0..100)
+                .map(|_| plan_chunk(start, 32.0, 1.0, rng).len())
+                .sum()
+        };
+        let early = count(0.0, &mut rng);
+        let late = count(10_000.0, &mut rng);
+        assert!(early < late);
+    }
+
+    #[test]
+    fn denser_chunks_have_more_enemies() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+        let count = |density: f32, rng: &mut rand::rngs::StdRng| -> usize {
+            (0..100)
+                .map(|_| plan_chunk(1000.0, 32.0, density, rng).len())
+                .sum()
+        };
+        let easy = count(0.6, &mut rng);
+        let insane = count(2.5, &mut rng);
+        assert!(easy < insane);
+    }
+}
@@ -1,30 +1,29 @@
 #[derive(bevy::ecs::component::Component)]
 pub struct Enemy;
 
-pub type EnemyBundle = (Enemy, crate::sprite::Sprite, crate::collider::Collider);
+pub type EnemyBundle = (
+    Enemy,
+    crate::sprite::Sprite,
+    crate::collider::Collider,
+    crate::collider::CollisionLayers,
+    crate::collider::Mask,
+);
 
 const ENEMY_SPEED: f32 = 30.0;
 
 impl Enemy {
     pub fn bundle() -> EnemyBundle {
+        let sprite = crate::sprite::Sprite::from_lines(&["//", "//", " o", "\\\\", "\\\\"])
+            .expect("valid enemy sprite");
         (
             Enemy,
-            crate::sprite::Sprite {
-                buffer: crate::buffer::Buffer(
-                    ndarray::array![
-                        [Some('/'), Some('/')],
-                        [Some('/'), Some('/')],
-                        [None, Some('o')],
-                        [Some('\\'), Some('\\')],
-                        [Some('\\'), Some('\\')],
-                    ]
-                    .map(|c| crate::buffer::Cell {
-                        character: *c,
-                        ..Default::default()
-                    }),
-                ),
-            },
+            sprite.clone(),
             crate::collider::Collider::new(2.0, 5.0),
+            crate::collider::CollisionLayers::new(
+                crate::collider::layer::ENEMY,
+                crate::collider::layer::PLAYER | crate::collider::layer::SHOT,
+            ),
+            crate::collider::Mask::from_sprite(&sprite),
         )
     }
 }
@@ -37,20 +36,15 @@ pub fn plugin(app: &mut bevy::app::App) {
     );
     app.add_systems(
         bevy::app::Update,
-        handle_player_enemy_collisions
-            .run_if(any_with_component::<Enemy>)
-            .run_if(any_with_component::<crate::player::Player>),
-    );
-    app.add_systems(
-        bevy::app::Update,
-        handle_enemy_shot
-            .run_if(any_with_component::<Enemy>)
-            .run_if(any_with_component::<crate::weapon::Shot>),
+        (handle_player_enemy_collisions, handle_enemy_shot)
+            .after(crate::collider::CollisionDetection)
+            .run_if(any_with_component::<Enemy>),
     );
 }
 
 fn update(
     time: bevy::ecs::system::Res<bevy::time::Time>,
+    scaling: bevy::ecs::system::Res<crate::difficulty::Scaling>,
     mut enemy_query: bevy::ecs::system::Query<
         (
             &mut bevy::transform::components::Transform,
@@ -71,78 +65,77 @@ fn update(
         return;
     };
 
+    let speed = ENEMY_SPEED * scaling.enemy_speed;
     for (mut enemy_transform, enemy_global_transform) in &mut enemy_query {
         let global_translation = enemy_global_transform.translation();
         let enemy_in_frame = global_translation.x
             < frame_global_transform.translation().x + frame_collider.x
             && global_translation.x > frame_global_transform.translation().x;
         if enemy_in_frame {
-            enemy_transform.translation.x -= ENEMY_SPEED * time.delta_seconds();
+            enemy_transform.translation.x -= speed * time.delta_seconds();
         }
     }
 }
 
 fn handle_player_enemy_collisions(
-    enemy_query: bevy::ecs::system::Query<
-        (
-            &crate::collider::Collider,
-            &bevy::transform::components::GlobalTransform,
-        ),
-        bevy::ecs::query::With<Enemy>,
-    >,
-    player_query: bevy::ecs::system::Query<
-        (
-            &crate::collider::Collider,
-            &bevy::transform::components::GlobalTransform,
-        ),
-        bevy::ecs::query::With<crate::player::Player>,
-    >,
-    mut app_event_sender: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
+    mut commands: bevy::ecs::system::Commands,
+    mut reader: bevy::ecs::event::EventReader<crate::collider::CollisionEvent>,
+    enemy_query: bevy::ecs::system::Query<(), bevy::ecs::query::With<Enemy>>,
+    mut player_query: bevy::ecs::system::Query<(
+        &crate::player::Player,
+        &mut crate::player::PlayerState,
+        bevy::ecs::query::Has<crate::player::Invulnerable>,
+        bevy::ecs::query::Has<crate::player::Recovering>,
+    )>,
+    mut level_event_sender: bevy::ecs::event::EventWriter<crate::level::LevelEvent>,
+    mut performance: bevy::ecs::system::ResMut<crate::difficulty::Performance>,
 ) {
-    let Ok(player) = player_query.get_single() else {
-        log::error!("Couldn't unique get player instance.");
-        return;
-    };
-
-    let mut collision = false;
-    for enemy in &enemy_query {
-        if crate::collider::collide(player, enemy) {
-            collision = true;
+    let mut crashed = std::collections::HashSet::new();
+    let mut destroyed = std::collections::HashSet::new();
+    for event in reader.read() {
+        let Some((player, enemy)) =
+            event.matching(|e| player_query.contains(e), |e| enemy_query.contains(e))
+        else {
+            continue;
+        };
+        let Ok((number, mut state, invulnerable, recovering)) = player_query.get_mut(player) else {
+            continue;
+        };
+        if invulnerable || recovering || !crashed.insert(player) {
+            continue;
+        }
+        // the enemy goes down with the ship
+        if destroyed.insert(enemy) {
+            commands.entity(enemy).despawn();
         }
+        performance.record_death();
+        crate::player::crash(&mut commands, player, *number, &mut state);
     }
 
-    if collision {
-        app_event_sender.send(bevy::app::AppExit);
+    if !crashed.is_empty() && player_query.iter().all(|(_, state, ..)| state.lives() == 0) {
+        log::info!("Game over");
+        level_event_sender.send(crate::level::LevelEvent::LevelEnd);
     }
 }
 
 fn handle_enemy_shot(
-    enemy_query: bevy::ecs::system::Query<
-        (
-            bevy::ecs::entity::Entity,
-            &crate::collider::Collider,
-            &bevy::transform::components::GlobalTransform,
-        ),
-        bevy::ecs::query::With<Enemy>,
-    >,
-    shot_query: bevy::ecs::system::Query<
-        (
-            bevy::ecs::entity::Entity,
-            &crate::collider::Collider,
-            &bevy::transform::components::GlobalTransform,
-        ),
-        bevy::ecs::query::With<crate::weapon::Shot>,
-    >,
+    mut reader: bevy::ecs::event::EventReader<crate::collider::CollisionEvent>,
+    enemy_query: bevy::ecs::system::Query<(), bevy::ecs::query::With<Enemy>>,
+    shot_query: bevy::ecs::system::Query<&crate::weapon::Shot>,
     mut commands: bevy::ecs::system::Commands,
+    mut score: bevy::ecs::system::ResMut<crate::score::Score>,
 ) {
-    for (enemy, enemy_collider, enemy_transform) in &enemy_query {
-        for (shot, shot_collider, shot_transform) in &shot_query {
-            if crate::collider::collide(
-                (shot_collider, shot_transform),
-                (enemy_collider, enemy_transform),
-            ) {
-                commands.entity(enemy).despawn();
-                commands.entity(shot).despawn();
+    let mut destroyed = std::collections::HashSet::new();
+    for event in reader.read() {
+        let Some((enemy, shot)) =
+            event.matching(|e| enemy_query.contains(e), |e| shot_query.contains(e))
+        else {
+            continue;
+        };
+        if destroyed.insert(enemy) {
+            commands.entity(enemy).despawn();
+            if let Ok(shot) = shot_query.get(shot) {
+                score.add_kill(shot.owner);
             }
         }
     }
@@ -1,5 +1,62 @@
-pub const WIDTH: usize = 128;
-pub const HEIGHT: usize = 32;
+/// The play field dimensions in cells, chosen once at startup.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Size {
+    pub width: usize,
+    pub height: usize,
+}
+
+pub const MIN_SIZE: Size = Size {
+    width: 64,
+    height: 16,
+};
+pub const MAX_SIZE: Size = Size {
+    width: 256,
+    height: 64,
+};
+
+impl Default for Size {
+    fn default() -> Self {
+        Self {
+            width: 128,
+            height: 32,
+        }
+    }
+}
+
+impl Size {
+    pub fn clamped(width: usize, height: usize) -> Self {
+        Self {
+            width: width.clamp(MIN_SIZE.width, MAX_SIZE.width),
+            height: height.clamp(MIN_SIZE.height, MAX_SIZE.height),
+        }
+    }
+
+    /// Pick the size from the requested dimensions, filling in any that
+    /// are missing from the terminal size, less room for the border.
+    pub fn choose(
+        width: Option<usize>,
+        height: Option<usize>,
+        terminal: Option<(u16, u16)>,
+    ) -> Self {
+        let fitted = terminal.map(|(columns, rows)| {
+            (
+                (columns as usize).saturating_sub(crate::terminal::BORDER_LENGTH as usize),
+                (rows as usize).saturating_sub(crate::terminal::BORDER_LENGTH as usize),
+            )
+        });
+        let default = Self::default();
+        Self::clamped(
+            width
+                .or(fitted.map(|(width, _)| width))
+                .unwrap_or(default.width),
+            height
+                .or(fitted.map(|(_, height)| height))
+                .unwrap_or(default.height),
+        )
+    }
+}
+/// Cells per second the frame scrolls through the level.
+const SPEED: f32 = 6.0;
 
 #[derive(bevy::ecs::component::Component)]
 pub struct Frame;
@@ -26,6 +83,7 @@ pub fn plugin(app: &mut bevy::app::App) {
         IntoSystemConfigs,
     };
 
+    app.init_resource::<Size>();
     app.insert_resource(FrameProgressTimer(bevy::time::Timer::new(
         std::time::Duration::from_secs(5),
         bevy::time::TimerMode::Repeating,
@@ -53,15 +111,13 @@ pub fn plugin(app: &mut bevy::app::App) {
             .run_if(any_with_component::<crate::level::Level>)
             .run_if(should_log_level_progress),
     );
-    app.add_systems(
-        bevy::app::Update,
-        update_log_level_timer,
-    );
+    app.add_systems(bevy::app::Update, update_log_level_timer);
 }
 
 // for now moves at constant speed
 // todo: move along a path defined by the level
 fn move_frame(
+    time: bevy::ecs::system::Res<bevy::time::Time>,
     mut query: bevy::ecs::system::Query<
         &mut bevy::transform::components::Transform,
         bevy::ecs::query::With<Frame>,
@@ -72,7 +128,7 @@ fn move_frame(
         return;
     };
 
-    transform.translation.x += 0.1;
+    transform.translation.x += SPEED * time.delta_seconds();
 }
 
 fn update_log_level_timer(
@@ -109,10 +165,14 @@ fn log_level_progress(
     let bevy::math::f32::Vec3 { x, .. } =
         frame_transform.transform_point(frame_collider.extend(0.0));
 
-    log::info!(
-        "Level progress: {:.1}%",
-        (x - frame_collider.x) * 100.0 / (level.length - frame_collider.x)
-    );
+    if level.length.is_finite() {
+        log::info!(
+            "Level progress: {:.1}%",
+            (x - frame_collider.x) * 100.0 / (level.length - frame_collider.x)
+        );
+    } else {
+        log::info!("Level distance: {:.0}", x - frame_collider.x);
+    }
 }
 
 fn end_level(
@@ -146,6 +206,7 @@ fn end_level(
 
 fn spawn(
     mut commands: bevy::ecs::system::Commands,
+    size: bevy::ecs::system::Res<Size>,
     query: bevy::ecs::system::Query<
         bevy::ecs::entity::Entity,
         bevy::ecs::query::With<crate::level::Level>,
@@ -156,13 +217,40 @@ fn spawn(
         return;
     };
 
-    log::info!("spawning Frame");
+    log::info!("spawning {}x{} Frame", size.width, size.height);
     use bevy::hierarchy::BuildChildren;
     commands
         .spawn((
             Frame,
             bevy::transform::TransformBundle::default(),
-            crate::collider::Collider::new(WIDTH as f32, HEIGHT as f32),
+            crate::collider::Collider::new(size.width as f32, size.height as f32),
         ))
         .set_parent(level);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn size_fits_terminal_within_bounds() {
+        assert_eq!(Size::choose(None, None, None), Size::default());
+        assert_eq!(
+            Size::choose(None, None, Some((100, 30))),
+            Size {
+                width: 98,
+                height: 28
+            }
+        );
+        assert_eq!(Size::choose(None, None, Some((20, 10))), MIN_SIZE);
+        assert_eq!(Size::choose(None, None, Some((1000, 1000))), MAX_SIZE);
+        assert_eq!(
+            Size::choose(Some(80), None, Some((200, 50))),
+            Size {
+                width: 80,
+                height: 48
+            }
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::input::{Action, ActionEvent};
+
+/// Fraction of the stick's travel around its centre which is ignored.
+const DEADZONE: f32 = 0.25;
+/// Fraction of the stick's deflection needed along an axis to hold that
+/// direction, sin(22.5°) so the stick splits into eight even sectors.
+const SECTOR: f32 = 0.383;
+
+const DIRECTIONS: [Action; 4] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveUp,
+    Action::MoveDown,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    StickX,
+    StickY,
+    DpadX,
+    DpadY,
+}
+
+/// Controller input, independent of the device it came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadInput {
+    /// An axis position, normalised to -1..=1 with positive right and down.
+    Axis(Axis, f32),
+    Button(Action, bool),
+}
+
+/// Translates controller input into the same action events as the keys.
+///
+/// The stick and d-pad hold the movement actions, so the player's
+/// `direction` normalises diagonals just as it does for the keyboard.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Gamepad {
+    stick: bevy::math::Vec2,
+    dpad: bevy::math::Vec2,
+    held: [bool; 4],
+}
+
+impl Gamepad {
+    pub fn apply(&mut self, input: GamepadInput) -> Vec<ActionEvent> {
+        let (axis, value) = match input {
+            GamepadInput::Button(action, pressed) => {
+                return vec![ActionEvent {
+                    action,
+                    pressed,
+                    player: 0,
+                }];
+            }
+            GamepadInput::Axis(axis, value) => (axis, value),
+        };
+        match axis {
+            Axis::StickX => self.stick.x = value,
+            Axis::StickY => self.stick.y = value,
+            Axis::DpadX => self.dpad.x = value,
+            Axis::DpadY => self.dpad.y = value,
+        }
+
+        let stick = held(self.stick);
+        let dpad = held(self.dpad);
+        let mut events = Vec::new();
+        for (i, action) in DIRECTIONS.into_iter().enumerate() {
+            let pressed = stick[i] || dpad[i];
+            if pressed != self.held[i] {
+                self.held[i] = pressed;
+                events.push(ActionEvent {
+                    action,
+                    pressed,
+                    player: 0,
+                });
+            }
+        }
+        events
+    }
+}
+
+/// Which of `DIRECTIONS` a stick position holds.
+fn held(stick: bevy::math::Vec2) -> [bool; 4] {
+    let length = stick.length();
+    if length <= DEADZONE {
+        return [false; 4];
+    }
+    let threshold = SECTOR * length;
+    [
+        stick.x < -threshold,
+        stick.x > threshold,
+        stick.y < -threshold,
+        stick.y > threshold,
+    ]
+}
+
+/// Receives input read from the controllers on background threads.
+#[derive(bevy::ecs::system::Resource)]
+pub struct GamepadSource(std::sync::Mutex<std::sync::mpsc::Receiver<GamepadInput>>);
+
+impl GamepadSource {
+    pub fn new(receiver: std::sync::mpsc::Receiver<GamepadInput>) -> Self {
+        Self(std::sync::Mutex::new(receiver))
+    }
+
+    /// Start reading every connected controller on its own thread.
+    pub fn open() -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut found = false;
+        for (path, device) in evdev::enumerate() {
+            if !is_gamepad(&device) {
+                continue;
+            }
+            log::info!(
+                "Reading gamepad {} from {}",
+                device.name().unwrap_or("unknown"),
+                path.display()
+            );
+            found = true;
+            let sender = sender.clone();
+            std::thread::spawn(move || read(device, sender));
+        }
+        if !found {
+            log::warn!("No readable gamepads found");
+        }
+        Self::new(receiver)
+    }
+
+    fn drain(&self) -> Vec<GamepadInput> {
+        match self.0.lock() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<Gamepad>();
+    app.insert_resource(GamepadSource::open());
+    app.add_systems(
+        bevy::app::PreUpdate,
+        dispatch.after(crate::terminal::handle_events),
+    );
+}
+
+fn dispatch(
+    source: bevy::ecs::system::Res<GamepadSource>,
+    mut gamepad: bevy::ecs::system::ResMut<Gamepad>,
+    mut writer: bevy::ecs::event::EventWriter<ActionEvent>,
+    screen: bevy::ecs::system::Res<crate::bindings_screen::BindingsScreen>,
+    console: bevy::ecs::system::Res<crate::console::Console>,
+) {
+    for input in source.drain() {
+        // keep tracking the stick so nothing sticks when the screen closes
+        let events = gamepad.apply(input);
+        if !screen.is_open() && !console.is_open() {
+            writer.send_batch(events);
+        }
+    }
+}
+
+fn is_gamepad(device: &evdev::Device) -> bool {
+    device.supported_keys().is_some_and(|keys| {
+        keys.contains(evdev::Key::BTN_SOUTH) || keys.contains(evdev::Key::BTN_TRIGGER)
+    })
+}
+
+fn read(mut device: evdev::Device, sender: std::sync::mpsc::Sender<GamepadInput>) {
+    let ranges: Vec<_> = match device.get_abs_state() {
+        Ok(state) => state
+            .iter()
+            .map(|info| (info.minimum, info.maximum))
+            .collect(),
+        Err(e) => {
+            log::error!("Couldn't read gamepad axes: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) => {
+                log::warn!("Stopped reading gamepad: {}", e);
+                return;
+            }
+        };
+        for event in events {
+            let Some(input) = translate(&event, &ranges) else {
+                continue;
+            };
+            if sender.send(input).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Map an evdev event onto controller input, with `ranges` holding the
+/// minimum and maximum of each absolute axis.
+fn translate(event: &evdev::InputEvent, ranges: &[(i32, i32)]) -> Option<GamepadInput> {
+    use evdev::{AbsoluteAxisType as Abs, InputEventKind, Key};
+
+    match event.kind() {
+        InputEventKind::AbsAxis(abs) => {
+            let axis = match abs {
+                Abs::ABS_X => Axis::StickX,
+                Abs::ABS_Y => Axis::StickY,
+                Abs::ABS_HAT0X => Axis::DpadX,
+                Abs::ABS_HAT0Y => Axis::DpadY,
+                _ => return None,
+            };
+            let (min, max) = *ranges.get(abs.0 as usize)?;
+            Some(GamepadInput::Axis(axis, normalise(event.value(), min, max)))
+        }
+        InputEventKind::Key(key) => {
+            let action = match key {
+                Key::BTN_SOUTH | Key::BTN_TRIGGER => Action::Fire,
+                Key::BTN_EAST | Key::BTN_THUMB => Action::Bomb,
+                Key::BTN_START => Action::Pause,
+                _ => return None,
+            };
+            // 2 is an auto-repeat
+            match event.value() {
+                0 => Some(GamepadInput::Button(action, false)),
+                1 => Some(GamepadInput::Button(action, true)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn normalise(value: i32, min: i32, max: i32) -> f32 {
+    if max <= min {
+        return 0.0;
+    }
+    let unit = (value - min) as f32 / (max - min) as f32;
+    (unit * 2.0 - 1.0).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn event(action: Action, pressed: bool) -> ActionEvent {
+        ActionEvent {
+            action,
+            pressed,
+            player: 0,
+        }
+    }
+
+    #[test]
+    fn stick_inside_deadzone_holds_nothing() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(gamepad.apply(GamepadInput::Axis(Axis::StickX, 0.2)), vec![]);
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickX, 0.9)),
+            vec![event(Action::MoveRight, true)]
+        );
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickX, 0.1)),
+            vec![event(Action::MoveRight, false)]
+        );
+    }
+
+    #[test]
+    fn stick_diagonal_holds_both_directions() {
+        let mut gamepad = Gamepad::default();
+        gamepad.apply(GamepadInput::Axis(Axis::StickX, -0.7));
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickY, 0.7)),
+            vec![event(Action::MoveDown, true)]
+        );
+        assert_eq!(gamepad.held, [true, false, false, true]);
+        // mostly left is just left
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickY, 0.2)),
+            vec![event(Action::MoveDown, false)]
+        );
+    }
+
+    #[test]
+    fn dpad_and_stick_combine() {
+        let mut gamepad = Gamepad::default();
+        gamepad.apply(GamepadInput::Axis(Axis::DpadY, -1.0));
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickY, -1.0)),
+            vec![]
+        );
+        assert_eq!(gamepad.apply(GamepadInput::Axis(Axis::DpadY, 0.0)), vec![]);
+        assert_eq!(
+            gamepad.apply(GamepadInput::Axis(Axis::StickY, 0.0)),
+            vec![event(Action::MoveUp, false)]
+        );
+    }
+
+    #[test]
+    fn translate_evdev_events() {
+        let ranges = vec![(0, 255); 0x40];
+        let abs = |axis: evdev::AbsoluteAxisType, value| {
+            evdev::InputEvent::new(evdev::EventType::ABSOLUTE, axis.0, value)
+        };
+        let key = |key: evdev::Key, value| {
+            evdev::InputEvent::new(evdev::EventType::KEY, key.code(), value)
+        };
+
+        assert_eq!(
+            translate(&abs(evdev::AbsoluteAxisType::ABS_X, 0), &ranges),
+            Some(GamepadInput::Axis(Axis::StickX, -1.0))
+        );
+        assert_eq!(
+            translate(&abs(evdev::AbsoluteAxisType::ABS_Y, 255), &ranges),
+            Some(GamepadInput::Axis(Axis::StickY, 1.0))
+        );
+        assert_eq!(
+            translate(&key(evdev::Key::BTN_SOUTH, 1), &ranges),
+            Some(GamepadInput::Button(Action::Fire, true))
+        );
+        assert_eq!(translate(&key(evdev::Key::BTN_SOUTH, 2), &ranges), None);
+        assert_eq!(translate(&key(evdev::Key::KEY_A, 1), &ranges), None);
+    }
+
+    #[test]
+    fn source_drains_mocked_input() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let source = GamepadSource::new(receiver);
+        sender
+            .send(GamepadInput::Button(Action::Pause, true))
+            .unwrap();
+        sender.send(GamepadInput::Axis(Axis::DpadX, 1.0)).unwrap();
+
+        let mut gamepad = Gamepad::default();
+        let events: Vec<_> = source
+            .drain()
+            .into_iter()
+            .flat_map(|input| gamepad.apply(input))
+            .collect();
+        assert_eq!(
+            events,
+            vec![event(Action::Pause, true), event(Action::MoveRight, true)]
+        );
+        assert_eq!(source.drain(), vec![]);
+    }
+}
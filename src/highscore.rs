@@ -0,0 +1,517 @@
+use crate::app::Screen;
+use crate::input::{Action, ActionEvent};
+
+/// Entries kept on each board.
+pub const BOARD_SIZE: usize = 10;
+/// Letters in a name, as on the arcade machines.
+const NAME_LENGTH: usize = 3;
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+/// Held while adding a score, from loading the table to saving it, so
+/// hosted games finishing together don't lose each other's scores.
+static UPDATING: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+}
+
+/// The best scores on each board, persisted as TOML in the data directory.
+#[derive(bevy::ecs::system::Resource, Clone, Debug, Default, PartialEq)]
+pub struct HighScores {
+    boards: std::collections::BTreeMap<String, Vec<Entry>>,
+}
+
+/// The board scores in `mode` at `difficulty` are kept on, with co-op
+/// teams on boards of their own.
+pub fn board(
+    mode: crate::level::Mode,
+    difficulty: crate::difficulty::Difficulty,
+    players: usize,
+) -> String {
+    use clap::ValueEnum;
+    let mode = mode
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default();
+    if players > 1 {
+        format!("{}-{}-{}p", mode, difficulty.name(), players)
+    } else {
+        format!("{}-{}", mode, difficulty.name())
+    }
+}
+
+impl HighScores {
+    pub fn path() -> Option<std::path::PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("shellaga").join("highscores.toml"))
+    }
+
+    /// Load the table, skipping any entries which can't be read. A file
+    /// which can't be read at all is moved aside rather than overwritten.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            log::warn!("No data directory, high scores won't be kept");
+            return Self::default();
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::error!("Couldn't read high scores {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+
+        match Self::from_toml(&contents) {
+            Ok(scores) => {
+                log::info!("Loaded high scores from {}", path.display());
+                scores
+            }
+            Err(e) => {
+                let mut backup = path.clone().into_os_string();
+                backup.push(".corrupt");
+                log::error!(
+                    "Couldn't parse high scores {}, moving them to {}: {}",
+                    path.display(),
+                    std::path::Path::new(&backup).display(),
+                    e
+                );
+                if let Err(e) = std::fs::rename(&path, &backup) {
+                    log::error!("Couldn't move high scores aside: {}", e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the table to a temporary file then move it into place, so an
+    /// interrupted save can't leave a truncated table behind.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // a file of our own, in case another thread or process is saving
+        let thread: String = format!("{:?}", std::thread::current().id())
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect();
+        let temporary = path.with_extension(format!("toml.{}.{}.tmp", std::process::id(), thread));
+        std::fs::write(&temporary, toml::to_string_pretty(&self.boards)?)?;
+        std::fs::rename(&temporary, &path)?;
+        log::info!("Saved high scores to {}", path.display());
+        Ok(())
+    }
+
+    fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::Table = toml::from_str(contents)?;
+        let mut scores = Self::default();
+        for (board, entries) in table {
+            let Some(entries) = entries.as_array() else {
+                log::warn!("Ignoring high score board {} which isn't a list", board);
+                continue;
+            };
+            for entry in entries {
+                match entry.clone().try_into::<Entry>() {
+                    Ok(entry) => {
+                        scores.insert(&board, entry);
+                    }
+                    Err(e) => log::warn!("Ignoring high score on {}: {}", board, e),
+                }
+            }
+        }
+        Ok(scores)
+    }
+
+    pub fn board(&self, board: &str) -> &[Entry] {
+        self.boards
+            .get(board)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn boards(&self) -> impl Iterator<Item = &str> {
+        self.boards.keys().map(String::as_str)
+    }
+
+    /// Whether `score` would make it onto the board.
+    pub fn qualifies(&self, board: &str, score: u32) -> bool {
+        let entries = self.board(board);
+        score > 0 && (entries.len() < BOARD_SIZE || entries.iter().any(|e| score > e.score))
+    }
+
+    /// Add an entry below any equal scores, returning its rank if it made
+    /// the board.
+    pub fn insert(&mut self, board: &str, entry: Entry) -> Option<usize> {
+        let entries = self.boards.entry(board.to_string()).or_default();
+        let rank = entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(entries.len());
+        if rank >= BOARD_SIZE {
+            return None;
+        }
+        entries.insert(rank, entry);
+        entries.truncate(BOARD_SIZE);
+        Some(rank)
+    }
+}
+
+/// Initials being entered for a new high score, picked letter by letter
+/// with the movement and fire actions so a controller works too.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct NameEntry {
+    /// Index into `ALPHABET` of each letter, kept between games.
+    letters: [usize; NAME_LENGTH],
+    cursor: usize,
+}
+
+impl NameEntry {
+    pub fn name(&self) -> String {
+        self.letters
+            .iter()
+            .map(|&letter| ALPHABET[letter] as char)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Returns true once the name is confirmed.
+    pub fn handle(&mut self, action: Action) -> bool {
+        let letter = &mut self.letters[self.cursor];
+        match action {
+            Action::MoveUp => *letter = (*letter + 1) % ALPHABET.len(),
+            Action::MoveDown => *letter = (*letter + ALPHABET.len() - 1) % ALPHABET.len(),
+            Action::MoveLeft => self.cursor = self.cursor.saturating_sub(1),
+            Action::MoveRight => self.cursor = (self.cursor + 1).min(NAME_LENGTH - 1),
+            Action::Fire if self.cursor + 1 < NAME_LENGTH => self.cursor += 1,
+            Action::Fire => return true,
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Which board the high score screen shows, and the entry to highlight.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Showing {
+    board: String,
+    latest: Option<usize>,
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter};
+
+    app.insert_resource(HighScores::load());
+    app.init_resource::<NameEntry>();
+    app.init_resource::<Showing>();
+    app.add_systems(OnEnter(Screen::NameEntry), begin_entry);
+    app.add_systems(OnEnter(Screen::HighScores), show_current_board);
+    app.add_systems(
+        bevy::app::Update,
+        (
+            enter_name.run_if(in_state(Screen::NameEntry)),
+            browse.run_if(in_state(Screen::HighScores)),
+        ),
+    );
+    app.add_systems(
+        bevy::app::PostUpdate,
+        (
+            render_entry.run_if(in_state(Screen::NameEntry)),
+            render_board.run_if(in_state(Screen::HighScores)),
+        ),
+    );
+}
+
+/// Skip straight to the board unless the score made it on.
+#[allow(clippy::too_many_arguments)]
+fn begin_entry(
+    scores: bevy::ecs::system::Res<HighScores>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    mode: bevy::ecs::system::Res<crate::level::Mode>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    players: bevy::ecs::system::Res<crate::player::Players>,
+    mut entry: bevy::ecs::system::ResMut<NameEntry>,
+    mut showing: bevy::ecs::system::ResMut<Showing>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    let board = board(*mode, config.difficulty, players.0);
+    showing.latest = None;
+    if scores.qualifies(&board, score.total()) {
+        log::info!("New high score {} on {}", score.total(), board);
+        entry.cursor = 0;
+    } else {
+        next_screen.set(Screen::HighScores);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn enter_name(
+    mut reader: bevy::ecs::event::EventReader<ActionEvent>,
+    mut scores: bevy::ecs::system::ResMut<HighScores>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    mode: bevy::ecs::system::Res<crate::level::Mode>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    players: bevy::ecs::system::Res<crate::player::Players>,
+    mut entry: bevy::ecs::system::ResMut<NameEntry>,
+    mut showing: bevy::ecs::system::ResMut<Showing>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    for event in reader.read().filter(|event| event.pressed) {
+        if event.action == Action::Quit {
+            next_screen.set(Screen::Title);
+            return;
+        }
+        if !entry.handle(event.action) {
+            continue;
+        }
+
+        // other games, such as hosted sessions, may have saved since
+        let _updating = UPDATING
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *scores = HighScores::load();
+        let board = board(*mode, config.difficulty, players.0);
+        showing.latest = scores.insert(
+            &board,
+            Entry {
+                name: entry.name(),
+                score: score.total(),
+            },
+        );
+        if let Err(e) = scores.save() {
+            log::error!("Failed to save high scores: {}", e);
+        }
+        next_screen.set(Screen::HighScores);
+        return;
+    }
+}
+
+fn show_current_board(
+    mode: bevy::ecs::system::Res<crate::level::Mode>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    players: bevy::ecs::system::Res<crate::player::Players>,
+    mut showing: bevy::ecs::system::ResMut<Showing>,
+) {
+    showing.board = board(*mode, config.difficulty, players.0);
+}
+
+/// Step through the boards, or go back to the title.
+fn browse(
+    mut reader: bevy::ecs::event::EventReader<ActionEvent>,
+    scores: bevy::ecs::system::Res<HighScores>,
+    mut showing: bevy::ecs::system::ResMut<Showing>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    for event in reader.read().filter(|event| event.pressed) {
+        let step = match event.action {
+            Action::MoveLeft => -1,
+            Action::MoveRight => 1,
+            Action::Fire | Action::Quit => {
+                showing.latest = None;
+                next_screen.set(Screen::Title);
+                return;
+            }
+            _ => continue,
+        };
+        let mut boards: Vec<_> = scores.boards().collect();
+        if !boards.contains(&showing.board.as_str()) {
+            boards.push(&showing.board);
+            boards.sort();
+        }
+        let Some(index) = boards.iter().position(|board| *board == showing.board) else {
+            continue;
+        };
+        let index = (index as isize + step).rem_euclid(boards.len() as isize) as usize;
+        showing.board = boards[index].to_string();
+        showing.latest = None;
+    }
+}
+
+fn render_entry(
+    entry: bevy::ecs::system::Res<NameEntry>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    use ratatui::style::Stylize;
+
+    let letters: Vec<_> = entry
+        .letters
+        .iter()
+        .enumerate()
+        .map(|(i, &letter)| {
+            let span = ratatui::text::Span::raw(format!(" {} ", ALPHABET[letter] as char));
+            if i == entry.cursor {
+                span.reversed()
+            } else {
+                span
+            }
+        })
+        .collect();
+    let total = score.total();
+
+    overlays.push(move |frame, area| {
+        let area = crate::terminal::centered(area, 30, 6);
+        let text = vec![
+            ratatui::text::Line::raw(format!("New high score {}", total)),
+            ratatui::text::Line::raw(""),
+            ratatui::text::Line::from(letters),
+        ];
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(text)
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(
+                    ratatui::widgets::Block::default()
+                        .title(" Game Over ")
+                        .title(
+                            ratatui::widgets::block::Title::from(" Fire to confirm ".bold())
+                                .position(ratatui::widgets::block::Position::Bottom),
+                        )
+                        .borders(ratatui::widgets::Borders::ALL),
+                ),
+            area,
+        );
+    });
+}
+
+fn render_board(
+    scores: bevy::ecs::system::Res<HighScores>,
+    showing: bevy::ecs::system::Res<Showing>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    let entries = scores.board(&showing.board).to_vec();
+    let title = format!(" High Scores: {} ", showing.board);
+    let latest = showing.latest;
+
+    overlays.push(move |frame, area| {
+        let area = crate::terminal::centered(area, 30, BOARD_SIZE as u16 + 2);
+        let rows = entries.into_iter().enumerate().map(|(i, entry)| {
+            let row = ratatui::widgets::Row::new([
+                format!("{:>2}.", i + 1),
+                entry.name,
+                entry.score.to_string(),
+            ]);
+            if Some(i) == latest {
+                row.style(
+                    ratatui::style::Style::new().add_modifier(ratatui::style::Modifier::REVERSED),
+                )
+            } else {
+                row
+            }
+        });
+        let table = ratatui::widgets::Table::new(
+            rows,
+            [
+                ratatui::layout::Constraint::Length(4),
+                ratatui::layout::Constraint::Fill(1),
+                ratatui::layout::Constraint::Length(8),
+            ],
+        )
+        .block(
+            ratatui::widgets::Block::default()
+                .title(title)
+                .title(
+                    ratatui::widgets::block::Title::from(" ←→ board  Fire back ")
+                        .position(ratatui::widgets::block::Position::Bottom),
+                )
+                .borders(ratatui::widgets::Borders::ALL),
+        );
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(table, area);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn entry(name: &str, score: u32) -> Entry {
+        Entry {
+            name: name.to_string(),
+            score,
+        }
+    }
+
+    #[test]
+    fn insert_keeps_boards_sorted_and_bounded() {
+        let mut scores = HighScores::default();
+        for score in 1..=BOARD_SIZE as u32 {
+            scores.insert("campaign", entry("AAA", score * 10));
+        }
+        assert!(!scores.qualifies("campaign", 10));
+        assert!(scores.qualifies("campaign", 11));
+        assert!(scores.qualifies("endless", 1));
+        assert!(!scores.qualifies("endless", 0));
+
+        // ties go below the existing score
+        assert_eq!(scores.insert("campaign", entry("BEN", 50)), Some(6));
+        assert_eq!(scores.board("campaign").len(), BOARD_SIZE);
+        assert_eq!(scores.board("campaign")[5], entry("AAA", 50));
+        assert_eq!(scores.board("campaign")[BOARD_SIZE - 1], entry("AAA", 20));
+        assert_eq!(scores.insert("campaign", entry("LOW", 1)), None);
+    }
+
+    #[test]
+    fn round_trip_through_toml() {
+        let mut scores = HighScores::default();
+        scores.insert("campaign", entry("BEN", 1200));
+        scores.insert("endless", entry("AL", 300));
+        let contents = toml::to_string_pretty(&scores.boards).unwrap();
+        assert_eq!(HighScores::from_toml(&contents).unwrap(), scores);
+    }
+
+    #[test]
+    fn bad_entries_are_skipped() {
+        let contents = r#"
+            version = 2
+
+            [[campaign]]
+            name = "BEN"
+            score = 1200
+
+            [[campaign]]
+            name = "BAD"
+            score = -5
+
+            [[campaign]]
+            score = 900
+
+            [[campaign]]
+            name = "AL"
+            score = 1500
+        "#;
+        let scores = HighScores::from_toml(contents).unwrap();
+        assert_eq!(
+            scores.board("campaign"),
+            &[entry("AL", 1500), entry("BEN", 1200)]
+        );
+        assert!(HighScores::from_toml("[[campaign]\nname =").is_err());
+    }
+
+    #[test]
+    fn name_entry_with_actions() {
+        let mut name = NameEntry::default();
+        for action in [
+            Action::MoveUp,
+            Action::MoveUp,
+            Action::MoveRight,
+            Action::MoveDown,
+            Action::MoveDown,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::Fire,
+        ] {
+            assert!(!name.handle(action));
+        }
+        assert_eq!(name.name(), "C9A");
+        assert!(name.handle(Action::Fire));
+
+        // trailing spaces are dropped
+        name.letters = [2, ALPHABET.len() - 1, ALPHABET.len() - 1];
+        assert_eq!(name.name(), "C");
+    }
+}
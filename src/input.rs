@@ -0,0 +1,508 @@
+/// Something the player can do, independent of which key does it.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Bomb,
+    Pause,
+    Debug,
+    Console,
+    Quit,
+    Bindings,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Bomb,
+        Action::Pause,
+        Action::Debug,
+        Action::Console,
+        Action::Quit,
+        Action::Bindings,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Fire => "Fire",
+            Action::Bomb => "Bomb",
+            Action::Pause => "Pause",
+            Action::Debug => "Debug Overlay",
+            Action::Console => "Console",
+            Action::Quit => "Quit",
+            Action::Bindings => "Key Bindings",
+        }
+    }
+}
+
+#[derive(bevy::ecs::event::Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionEvent {
+    pub action: Action,
+    pub pressed: bool,
+    /// Which player did it, numbered from 0.
+    pub player: usize,
+}
+
+/// A key which can be bound to an action, stored in the config file by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub crossterm::event::KeyCode);
+
+const NAMED_KEYS: [(&str, crossterm::event::KeyCode); 20] = [
+    ("Space", crossterm::event::KeyCode::Char(' ')),
+    ("Up", crossterm::event::KeyCode::Up),
+    ("Down", crossterm::event::KeyCode::Down),
+    ("Left", crossterm::event::KeyCode::Left),
+    ("Right", crossterm::event::KeyCode::Right),
+    ("Enter", crossterm::event::KeyCode::Enter),
+    ("Esc", crossterm::event::KeyCode::Esc),
+    ("Tab", crossterm::event::KeyCode::Tab),
+    ("Backspace", crossterm::event::KeyCode::Backspace),
+    ("Delete", crossterm::event::KeyCode::Delete),
+    ("Home", crossterm::event::KeyCode::Home),
+    ("End", crossterm::event::KeyCode::End),
+    ("PageUp", crossterm::event::KeyCode::PageUp),
+    ("PageDown", crossterm::event::KeyCode::PageDown),
+    // modifiers on their own are only reported with keyboard enhancement
+    (
+        "LeftCtrl",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::LeftControl),
+    ),
+    (
+        "RightCtrl",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::RightControl),
+    ),
+    (
+        "LeftShift",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::LeftShift),
+    ),
+    (
+        "RightShift",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::RightShift),
+    ),
+    (
+        "LeftAlt",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::LeftAlt),
+    ),
+    (
+        "RightAlt",
+        crossterm::event::KeyCode::Modifier(crossterm::event::ModifierKeyCode::RightAlt),
+    ),
+];
+
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, code)) = NAMED_KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(Key(*code));
+        }
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key(crossterm::event::KeyCode::Char(c)));
+        }
+        match s.strip_prefix(['F', 'f']).map(str::parse::<u8>) {
+            Some(Ok(n)) if (1..=12).contains(&n) => Ok(Key(crossterm::event::KeyCode::F(n))),
+            _ => Err(format!("unknown key \"{}\"", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, code)| *code == self.0) {
+            return write!(f, "{}", name);
+        }
+        match self.0 {
+            crossterm::event::KeyCode::Char(c) => write!(f, "{}", c),
+            crossterm::event::KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+/// The keys bound to each action.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Bindings(std::collections::BTreeMap<Action, Vec<Key>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use crossterm::event::KeyCode::*;
+        let bindings = [
+            (Action::MoveUp, vec![Char('w'), Up, Char('k')]),
+            (Action::MoveDown, vec![Char('s'), Down, Char('j')]),
+            (Action::MoveLeft, vec![Char('a'), Left, Char('h')]),
+            (Action::MoveRight, vec![Char('d'), Right, Char('l')]),
+            (Action::Fire, vec![Char(' ')]),
+            (Action::Bomb, vec![Char('b')]),
+            (Action::Pause, vec![Char('p')]),
+            (Action::Debug, vec![F(3)]),
+            (Action::Console, vec![Char('`')]),
+            (Action::Quit, vec![Esc]),
+            (Action::Bindings, vec![F(1)]),
+        ];
+        Self(
+            bindings
+                .into_iter()
+                .map(|(action, codes)| (action, codes.into_iter().map(Key).collect()))
+                .collect(),
+        )
+    }
+}
+
+impl Bindings {
+    /// Defaults for the second ship in co-op, which only moves and fires.
+    pub fn player_two() -> Self {
+        use crossterm::event::KeyCode::*;
+        use crossterm::event::ModifierKeyCode::RightControl;
+        let bindings = [
+            (Action::MoveUp, vec![Up]),
+            (Action::MoveDown, vec![Down]),
+            (Action::MoveLeft, vec![Left]),
+            (Action::MoveRight, vec![Right]),
+            (Action::Fire, vec![Modifier(RightControl), Enter]),
+        ];
+        Self(
+            bindings
+                .into_iter()
+                .map(|(action, codes)| (action, codes.into_iter().map(Key).collect()))
+                .collect(),
+        )
+    }
+
+    pub fn action(&self, code: crossterm::event::KeyCode) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.contains(&Key(code)))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Bind `key` to `action`, unbinding it from any other action.
+    pub fn bind(&mut self, action: Action, key: Key) {
+        for keys in self.0.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.0.entry(action).or_default().push(key);
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.0.remove(&action);
+    }
+
+    /// Replace the keys of every action present in `overrides`.
+    pub fn merge(&mut self, overrides: Bindings) {
+        for (action, keys) in overrides.0 {
+            for key in &keys {
+                for other in self.0.values_mut() {
+                    other.retain(|k| k != key);
+                }
+            }
+            self.0.insert(action, keys);
+        }
+    }
+}
+
+/// How long a key counts as held after its first press: long enough to
+/// cover the delay before a terminal starts auto-repeating it.
+const INITIAL_REPEAT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(550);
+/// How long a key counts as held after each auto-repeat.
+const REPEAT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Synthesises key releases for terminals which only report presses.
+///
+/// A key counts as held for as long as it keeps auto-repeating and is
+/// released once the repeats stop arriving.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct KeyRepeat {
+    held: std::collections::HashMap<crossterm::event::KeyCode, HeldKey>,
+}
+
+#[derive(Debug)]
+struct HeldKey {
+    last_press: std::time::Duration,
+    repeating: bool,
+}
+
+impl KeyRepeat {
+    /// Returns true if the key wasn't already held.
+    pub fn press(&mut self, code: crossterm::event::KeyCode, now: std::time::Duration) -> bool {
+        match self.held.get_mut(&code) {
+            Some(held) => {
+                held.last_press = now;
+                held.repeating = true;
+                false
+            }
+            None => {
+                self.held.insert(
+                    code,
+                    HeldKey {
+                        last_press: now,
+                        repeating: false,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// Forget and return the keys which have stopped repeating.
+    pub fn release_expired(&mut self, now: std::time::Duration) -> Vec<crossterm::event::KeyCode> {
+        let mut released = Vec::new();
+        self.held.retain(|code, held| {
+            let timeout = if held.repeating {
+                REPEAT_TIMEOUT
+            } else {
+                INITIAL_REPEAT_TIMEOUT
+            };
+            let expired = now.saturating_sub(held.last_press) > timeout;
+            if expired {
+                released.push(*code);
+            }
+            !expired
+        });
+        released
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.add_event::<ActionEvent>();
+    app.init_resource::<KeyRepeat>();
+    app.init_resource::<crate::terminal::Capabilities>();
+    app.add_systems(
+        bevy::app::PreUpdate,
+        dispatch.after(crate::terminal::handle_events),
+    );
+}
+
+/// Forward key events to the bindings screen or console while either is
+/// open, otherwise translate them into action events.
+#[allow(clippy::too_many_arguments)]
+fn dispatch(
+    mut reader: bevy::ecs::event::EventReader<crate::terminal::TerminalEvent>,
+    mut writer: bevy::ecs::event::EventWriter<ActionEvent>,
+    mut config: bevy::ecs::system::ResMut<crate::config::Config>,
+    persist: bevy::ecs::system::Res<crate::config::Persist>,
+    mut screen: bevy::ecs::system::ResMut<crate::bindings_screen::BindingsScreen>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
+    real_time: bevy::ecs::system::Res<bevy::time::Time<bevy::time::Real>>,
+    capabilities: bevy::ecs::system::Res<crate::terminal::Capabilities>,
+    mut repeat: bevy::ecs::system::ResMut<KeyRepeat>,
+    mut console: Option<bevy::ecs::system::ResMut<crate::console::Console>>,
+    players: bevy::ecs::system::Res<crate::player::Players>,
+) {
+    let now = real_time.elapsed();
+    for event in reader.read() {
+        let crate::terminal::TerminalEvent::Key(key) = event else {
+            continue;
+        };
+
+        if screen.is_open() {
+            if !screen.handle_key(key, &mut config.bindings) {
+                screen.close(&mut time);
+                if persist.0 {
+                    if let Err(e) = config.save() {
+                        log::error!("Failed to save config: {}", e);
+                    }
+                }
+            }
+            continue;
+        }
+
+        if let Some(console) = console.as_mut().filter(|console| console.is_open()) {
+            if !console.handle_key(key, &config.bindings) {
+                console.close(&mut time);
+            }
+            continue;
+        }
+
+        let pressed = if capabilities.keyboard_enhancement {
+            match key.kind {
+                crossterm::event::KeyEventKind::Press => true,
+                crossterm::event::KeyEventKind::Release => false,
+                crossterm::event::KeyEventKind::Repeat => continue,
+            }
+        } else if repeat.press(key.code, now) {
+            true
+        } else {
+            continue;
+        };
+
+        let Some((player, action)) = config.action(key.code, players.0) else {
+            continue;
+        };
+
+        if pressed && matches!(action, Action::Bindings | Action::Console) {
+            if action == Action::Bindings {
+                screen.open(&mut time);
+            } else if let Some(console) = console.as_mut() {
+                console.open(&mut time);
+            }
+            // nothing is held while the screen has the keyboard
+            repeat.clear();
+            writer.send_batch((0..players.0).flat_map(|player| {
+                Action::ALL.map(|action| ActionEvent {
+                    action,
+                    pressed: false,
+                    player,
+                })
+            }));
+            continue;
+        }
+
+        writer.send(ActionEvent {
+            action,
+            pressed,
+            player,
+        });
+    }
+
+    for code in repeat.release_expired(now) {
+        if let Some((player, action)) = config.action(code, players.0) {
+            writer.send(ActionEvent {
+                action,
+                pressed: false,
+                player,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn ms(millis: u64) -> std::time::Duration {
+        std::time::Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn tapped_key_releases_after_initial_timeout() {
+        let mut repeat = KeyRepeat::default();
+        let w = crossterm::event::KeyCode::Char('w');
+        assert!(repeat.press(w, ms(0)));
+        assert!(repeat.release_expired(ms(500)).is_empty());
+        assert_eq!(repeat.release_expired(ms(600)), vec![w]);
+    }
+
+    #[test]
+    fn repeating_key_stays_held() {
+        let mut repeat = KeyRepeat::default();
+        let w = crossterm::event::KeyCode::Char('w');
+        assert!(repeat.press(w, ms(0)));
+        // typical auto-repeat: a delay then a press every 33ms
+        for t in (500..2000).step_by(33) {
+            assert!(!repeat.press(w, ms(t)));
+            assert!(repeat.release_expired(ms(t + 20)).is_empty());
+        }
+        assert_eq!(repeat.release_expired(ms(2200)), vec![w]);
+    }
+
+    #[test]
+    fn released_key_can_be_pressed_again() {
+        let mut repeat = KeyRepeat::default();
+        let w = crossterm::event::KeyCode::Char('w');
+        assert!(repeat.press(w, ms(0)));
+        assert_eq!(repeat.release_expired(ms(1000)), vec![w]);
+        assert!(repeat.press(w, ms(1100)));
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for name in [
+            "w",
+            "Space",
+            "Up",
+            "Esc",
+            "F1",
+            "F12",
+            "PageDown",
+            "~",
+            "RightCtrl",
+        ] {
+            assert_eq!(name.parse::<Key>().unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn key_names_are_case_insensitive() {
+        assert_eq!("space".parse::<Key>(), "Space".parse::<Key>());
+        assert_eq!("f3".parse::<Key>(), "F3".parse::<Key>());
+    }
+
+    #[test]
+    fn unknown_key_names_are_rejected() {
+        assert!("F13".parse::<Key>().is_err());
+        assert!("Hyper".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn default_bindings_include_arrows_and_vim_keys() {
+        use crossterm::event::KeyCode::*;
+        let bindings = Bindings::default();
+        assert_eq!(bindings.action(Up), Some(Action::MoveUp));
+        assert_eq!(bindings.action(Char('k')), Some(Action::MoveUp));
+        assert_eq!(bindings.action(Char('h')), Some(Action::MoveLeft));
+        assert_eq!(bindings.action(Esc), Some(Action::Quit));
+        assert_eq!(bindings.action(Char('z')), None);
+    }
+
+    #[test]
+    fn bind_steals_key_from_other_action() {
+        use crossterm::event::KeyCode::*;
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Fire, Key(Char('k')));
+        assert_eq!(bindings.action(Char('k')), Some(Action::Fire));
+        assert!(!bindings.keys(Action::MoveUp).contains(&Key(Char('k'))));
+    }
+
+    #[test]
+    fn merge_overrides_only_given_actions() {
+        use crossterm::event::KeyCode::*;
+        let mut bindings = Bindings::default();
+        let overrides: Bindings = toml::from_str("fire = [\"Enter\", \"x\"]").unwrap();
+        bindings.merge(overrides);
+        assert_eq!(bindings.keys(Action::Fire), &[Key(Enter), Key(Char('x'))]);
+        assert_eq!(bindings.action(Char('w')), Some(Action::MoveUp));
+    }
+}
@@ -3,9 +3,32 @@ pub struct Level {
     pub length: f32,
 }
 
-const BACKGROUND_DEPTH: f32 = 10.0;
-const BACKGROUND_DENSITY: f32 = 0.97;
-const ENEMY_DENSITY: f32 = 0.95;
+/// Chance of an enemy in each column of a campaign level, before scaling.
+const ENEMY_DENSITY: f32 = 0.05;
+const CAMPAIGN_LENGTH: f32 = 1000.0;
+
+#[derive(
+    bevy::ecs::system::Resource, clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub enum Mode {
+    /// A fixed length level which ends once the frame reaches the end
+    #[default]
+    Campaign,
+    /// The frame scrolls forever through procedurally generated waves
+    Endless,
+}
+
+/// Generates enemy placement, seeded from entropy unless seeded again to
+/// reproduce a run.
+#[derive(bevy::ecs::system::Resource)]
+pub struct Rng(pub rand::rngs::StdRng);
+
+impl Default for Rng {
+    fn default() -> Self {
+        use rand::SeedableRng;
+        Self(rand::rngs::StdRng::from_entropy())
+    }
+}
 
 #[derive(bevy::ecs::event::Event, std::cmp::PartialEq, std::cmp::Eq)]
 pub enum LevelEvent {
@@ -17,6 +40,12 @@ pub fn plugin(app: &mut bevy::app::App) {
     use bevy::ecs::schedule::{common_conditions::any_with_component, IntoSystemConfigs};
 
     app.add_event::<LevelEvent>();
+    app.init_resource::<Mode>();
+    app.init_resource::<Rng>();
+    app.add_systems(
+        bevy::ecs::schedule::OnEnter(crate::app::Screen::Playing),
+        start,
+    );
     app.add_systems(
         bevy::app::Update,
         spawn.run_if(level_not_spawned).run_if(on_level_start_event),
@@ -29,6 +58,10 @@ pub fn plugin(app: &mut bevy::app::App) {
     );
 }
 
+fn start(mut writer: bevy::ecs::event::EventWriter<LevelEvent>) {
+    writer.send(LevelEvent::LevelStart);
+}
+
 fn level_not_spawned(query: bevy::ecs::system::Query<(), bevy::ecs::query::With<Level>>) -> bool {
     query.is_empty()
 }
@@ -41,46 +74,40 @@ fn on_level_end_event(mut events: bevy::ecs::event::EventReader<LevelEvent>) ->
     events.read().any(|e| *e == LevelEvent::LevelEnd)
 }
 
-fn spawn(mut commands: bevy::ecs::system::Commands) {
-    log::info!("spawning level");
-    let length = 1000.0;
+fn spawn(
+    mut commands: bevy::ecs::system::Commands,
+    mode: bevy::ecs::system::Res<Mode>,
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    mut rng: bevy::ecs::system::ResMut<Rng>,
+    scaling: bevy::ecs::system::Res<crate::difficulty::Scaling>,
+) {
+    log::info!("spawning {:?} level", *mode);
+    let length = match *mode {
+        Mode::Campaign => CAMPAIGN_LENGTH,
+        Mode::Endless => f32::INFINITY,
+    };
     let level = commands
         .spawn((
             bevy::transform::TransformBundle::default(),
             Level { length },
         ))
         .id();
-    spawn_background(&mut commands, level, length);
-    spawn_enemies(&mut commands, level, length);
-}
-
-fn spawn_background(
-    commands: &mut bevy::ecs::system::Commands,
-    parent: bevy::ecs::entity::Entity,
-    length: f32,
-) {
-    use bevy::hierarchy::BuildChildren;
-    use itertools::Itertools;
-    for (row, col) in (0..length as usize).cartesian_product(0..crate::frame::HEIGHT) {
-        if rand::random::<f32>() < BACKGROUND_DENSITY {
-            continue;
+    crate::background::spawn_layers(&mut commands, level);
+    commands.insert_resource(crate::score::Score::default());
+    match *mode {
+        Mode::Campaign => spawn_enemies(
+            &mut commands,
+            level,
+            length,
+            *size,
+            ENEMY_DENSITY * scaling.enemy_density,
+            &mut rng.0,
+        ),
+        Mode::Endless => {
+            commands
+                .entity(level)
+                .insert(crate::endless::Endless::new(size.width as f32));
         }
-
-        commands
-            .spawn((
-                bevy::transform::TransformBundle::from_transform(
-                    bevy::transform::components::Transform::from_translation(
-                        bevy::math::f32::Vec3::new(row as f32, col as f32, BACKGROUND_DEPTH),
-                    ),
-                ),
-                crate::sprite::Sprite {
-                    buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                        character: Some('*'),
-                        ..Default::default()
-                    }]]),
-                },
-            ))
-            .set_parent(parent);
     }
 }
 
@@ -88,18 +115,25 @@ fn spawn_enemies(
     commands: &mut bevy::ecs::system::Commands,
     parent: bevy::ecs::entity::Entity,
     length: f32,
+    size: crate::frame::Size,
+    density: f32,
+    rng: &mut impl rand::Rng,
 ) {
     use bevy::hierarchy::BuildChildren;
-    for i in crate::frame::WIDTH..length as usize {
-        if rand::random::<f32>() < ENEMY_DENSITY {
+    for i in size.width..length as usize {
+        if rng.gen::<f32>() >= density {
             continue;
         }
         commands
             .spawn(crate::enemy::Enemy::bundle())
             .insert(bevy::transform::TransformBundle::from_transform(
-                bevy::transform::components::Transform::from_translation(bevy::math::f32::Vec3::new(
-                    i as f32, rand::random::<f32>() * (crate::frame::HEIGHT as f32), 0.0,
-                )),
+                bevy::transform::components::Transform::from_translation(
+                    bevy::math::f32::Vec3::new(
+                        i as f32,
+                        rng.gen::<f32>() * (size.height as f32),
+                        0.0,
+                    ),
+                ),
             ))
             .set_parent(parent);
     }
@@ -135,7 +169,7 @@ fn teardown_level(
         &bevy::hierarchy::Parent,
     )>,
     level_query: bevy::ecs::system::Query<bevy::ecs::entity::Entity, bevy::ecs::query::With<Level>>,
-    mut app_event_sender: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<crate::app::Screen>>,
 ) {
     log::info!("Teardown level");
 
@@ -145,5 +179,5 @@ fn teardown_level(
     };
 
     despawn_with_children(&mut commands, entity, &entities_with_parent_query);
-    app_event_sender.send(bevy::app::AppExit);
+    next_screen.set(crate::app::Screen::NameEntry);
 }
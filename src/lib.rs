@@ -0,0 +1,35 @@
+pub mod app;
+pub mod background;
+pub mod bindings_screen;
+pub mod buffer;
+pub mod cli;
+pub mod client;
+pub mod collider;
+pub mod config;
+pub mod console;
+pub mod debug;
+pub mod difficulty;
+pub mod endless;
+pub mod enemy;
+pub mod frame;
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+pub mod gamepad;
+pub mod highscore;
+pub mod input;
+pub mod level;
+pub mod logging;
+pub mod mouse;
+pub mod net;
+pub mod player;
+pub mod score;
+pub mod server;
+pub mod session;
+pub mod settings;
+pub mod spectate;
+pub mod sprite;
+pub mod subcell;
+pub mod telnet;
+pub mod terminal;
+pub mod title;
+pub mod watch;
+pub mod weapon;
@@ -1,44 +1,331 @@
-#[cfg(target_os = "macos")]
-pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    oslog::OsLogger::new("com.benleadbetter.shellaga")
-        .level_filter(level())
-        .init()?;
-    Ok(())
+use std::io::Write;
+
+/// Rotate the log file once it would grow past this many bytes.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// How many rotated files to keep alongside the current one.
+const ROTATED_FILES: usize = 3;
+
+/// Where log records are written, chosen with `SHELLAGA_LOG_TARGET`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Target {
+    /// `shellaga.log` in the state directory
+    #[default]
+    File,
+    /// The system log, on unix
+    Syslog,
+    /// Held in memory and printed to stderr once the game exits, so the
+    /// records don't scribble over the screen
+    Stderr,
 }
 
-#[cfg(target_os = "windows")]
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    eventlog::init("shellaga", level())?;
+    let level = level();
+    let logger: Box<dyn log::Log> = match target() {
+        Target::File => match path().and_then(|path| FileLogger::open(path, level)) {
+            Ok(logger) => Box::new(logger),
+            Err(e) => {
+                let logger = StderrLogger::new(level);
+                logger.push(format!(
+                    "Couldn't open the log file, logging to stderr: {}",
+                    e
+                ));
+                Box::new(logger)
+            }
+        },
+        Target::Syslog => syslog(level)?,
+        Target::Stderr => Box::new(StderrLogger::new(level)),
+    };
+    log::set_boxed_logger(logger).map(|()| log::set_max_level(level))?;
     Ok(())
 }
 
+/// Flush the logger, printing held records if logging to stderr. Call once
+/// the terminal has been restored.
+pub fn finish() {
+    log::logger().flush();
+}
 
-#[cfg(target_os = "linux")]
-pub fn init() -> Result<(), Box<dyn std::error::Error>> {
+#[cfg(unix)]
+fn syslog(level: log::LevelFilter) -> Result<Box<dyn log::Log>, Box<dyn std::error::Error>> {
     let formatter = syslog::Formatter3164 {
         facility: syslog::Facility::LOG_USER,
         hostname: None,
         process: "shellaga".into(),
-        pid: 0,
+        pid: std::process::id(),
     };
-
     let logger = syslog::unix(formatter)?;
-    log::set_boxed_logger(Box::new(syslog::BasicLogger::new(logger)))
-        .map(|()| log::set_max_level(level()))?;
-    Ok(())
+    Ok(Box::new(Filtered {
+        level,
+        inner: syslog::BasicLogger::new(logger),
+    }))
+}
+
+#[cfg(not(unix))]
+fn syslog(_level: log::LevelFilter) -> Result<Box<dyn log::Log>, Box<dyn std::error::Error>> {
+    Err("syslog is only available on unix".into())
 }
 
 fn level() -> log::LevelFilter {
-    match std::env::var("SHELLAGA_LOG_LEVEL") {
-        Ok(value) => match &*value.to_lowercase() {
-            "off" => log::LevelFilter::Off,
-            "error" => log::LevelFilter::Error,
-            "warn" => log::LevelFilter::Warn,
-            "info" => log::LevelFilter::Info,
-            "debug" => log::LevelFilter::Debug,
-            "trace" => log::LevelFilter::Trace,
-            _ => log::LevelFilter::Warn,
-        },
-        Err(_) => log::LevelFilter::Warn,
+    parse_level(std::env::var("SHELLAGA_LOG_LEVEL").ok().as_deref())
+}
+
+fn target() -> Target {
+    parse_target(std::env::var("SHELLAGA_LOG_TARGET").ok().as_deref())
+}
+
+fn parse_level(value: Option<&str>) -> log::LevelFilter {
+    match value.map(|value| value.trim().to_lowercase()).as_deref() {
+        Some("off") => log::LevelFilter::Off,
+        Some("error") => log::LevelFilter::Error,
+        Some("warn") => log::LevelFilter::Warn,
+        Some("info") => log::LevelFilter::Info,
+        Some("debug") => log::LevelFilter::Debug,
+        Some("trace") => log::LevelFilter::Trace,
+        _ => log::LevelFilter::Warn,
+    }
+}
+
+fn parse_target(value: Option<&str>) -> Target {
+    match value.map(|value| value.trim().to_lowercase()).as_deref() {
+        Some("syslog") => Target::Syslog,
+        Some("stderr") | Some("stderr-after-exit") => Target::Stderr,
+        _ => Target::File,
+    }
+}
+
+/// `$XDG_STATE_HOME/shellaga/shellaga.log`, or the local data directory on
+/// platforms without a state directory.
+fn path() -> std::io::Result<std::path::PathBuf> {
+    let dir = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .ok_or_else(|| std::io::Error::other("no state directory"))?
+        .join("shellaga");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join("shellaga.log"))
+}
+
+fn format(record: &log::Record) -> String {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{}.{:03} {:<5} {}: {}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_millis(),
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// Applies the level to a logger which doesn't filter by itself.
+#[cfg(unix)]
+struct Filtered<L> {
+    level: log::LevelFilter,
+    inner: L,
+}
+
+#[cfg(unix)]
+impl<L: log::Log> log::Log for Filtered<L> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// A log file which is moved aside to `.1`, `.2` and so on as it fills up.
+#[derive(Debug)]
+struct RotatingFile {
+    path: std::path::PathBuf,
+    file: std::fs::File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: std::path::PathBuf, max_size: u64, keep: usize) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            keep,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let length = line.len() as u64 + 1;
+        if self.size > 0 && self.size + length > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            std::path::PathBuf::from(name)
+        };
+        if self.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated(n);
+                if from.exists() {
+                    std::fs::rename(from, rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(1))?;
+        }
+        *self = Self::open(self.path.clone(), self.max_size, self.keep)?;
+        Ok(())
+    }
+}
+
+struct FileLogger {
+    level: log::LevelFilter,
+    file: std::sync::Mutex<RotatingFile>,
+}
+
+impl FileLogger {
+    fn open(path: std::path::PathBuf, level: log::LevelFilter) -> std::io::Result<Self> {
+        Ok(Self {
+            level,
+            file: std::sync::Mutex::new(RotatingFile::open(path, MAX_FILE_SIZE, ROTATED_FILES)?),
+        })
+    }
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            // nowhere left to report a failure to log
+            let _ = file.write_line(&format(record));
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Holds records until `flush`, since the game owns the terminal while it
+/// runs.
+struct StderrLogger {
+    level: log::LevelFilter,
+    records: std::sync::Mutex<Vec<String>>,
+}
+
+impl StderrLogger {
+    fn new(level: log::LevelFilter) -> Self {
+        Self {
+            level,
+            records: Default::default(),
+        }
+    }
+
+    fn push(&self, line: String) {
+        if let Ok(mut records) = self.records.lock() {
+            records.push(line);
+        }
+    }
+}
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.push(format(record));
+        }
+    }
+
+    fn flush(&self) {
+        let Ok(mut records) = self.records.lock() else {
+            return;
+        };
+        let mut stderr = std::io::stderr().lock();
+        for line in records.drain(..) {
+            let _ = writeln!(stderr, "{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn levels() {
+        assert_eq!(parse_level(Some("off")), log::LevelFilter::Off);
+        assert_eq!(parse_level(Some("DEBUG")), log::LevelFilter::Debug);
+        assert_eq!(parse_level(Some(" trace\n")), log::LevelFilter::Trace);
+        assert_eq!(parse_level(Some("verbose")), log::LevelFilter::Warn);
+        assert_eq!(parse_level(None), log::LevelFilter::Warn);
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(parse_target(Some("syslog")), Target::Syslog);
+        assert_eq!(parse_target(Some("stderr-after-exit")), Target::Stderr);
+        assert_eq!(parse_target(Some("Stderr")), Target::Stderr);
+        assert_eq!(parse_target(Some("journald")), Target::File);
+        assert_eq!(parse_target(None), Target::File);
+    }
+
+    #[test]
+    fn rotates_full_files() {
+        let dir = std::env::temp_dir().join(format!("shellaga-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shellaga.log");
+
+        let mut file = RotatingFile::open(path.clone(), 8, 2).unwrap();
+        for line in ["one", "two", "three", "four", "five"] {
+            file.write_line(line).unwrap();
+        }
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("shellaga.log"), "five\n");
+        assert_eq!(read("shellaga.log.1"), "four\n");
+        assert_eq!(read("shellaga.log.2"), "three\n");
+        assert!(!dir.join("shellaga.log.3").exists());
+
+        // picks up where it left off
+        let mut file = RotatingFile::open(path, 8, 2).unwrap();
+        file.write_line("six").unwrap();
+        assert_eq!(read("shellaga.log"), "six\n");
+        assert_eq!(read("shellaga.log.1"), "five\n");
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -1,43 +1,179 @@
 use bevy::app::PluginGroup;
 
-mod app;
-mod buffer;
-mod collider;
-mod enemy;
-mod frame;
-mod level;
-mod logging;
-mod player;
-mod sprite;
-mod terminal;
-mod weapon;
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+use shellaga::gamepad;
+use shellaga::{
+    app, background, bindings_screen, buffer, cli, client, collider, config, console, debug,
+    difficulty, endless, enemy, frame, highscore, input, level, logging, mouse, player, score,
+    server, session, settings, spectate, sprite, subcell, terminal, title, watch, weapon,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     logging::init()?;
 
-    bevy::app::App::new()
-        .add_plugins(bevy::MinimalPlugins.set(runloop()))
-        .add_plugins(bevy::transform::TransformPlugin)
+    use clap::Parser;
+    let args = cli::Args::parse();
+    if let Some(cli::Command::Watch { address }) = &args.command {
+        let watched = watch::run(address);
+        logging::finish();
+        return watched;
+    }
+    if let Some(cli::Command::Host { address, sessions }) = &args.command {
+        if args.spectate.is_some() {
+            return Err("hosted games can't be spectated".into());
+        }
+        let listener = std::net::TcpListener::bind(address)?;
+        println!(
+            "Hosting on {}, play with telnet, up to {} at once",
+            listener.local_addr()?,
+            sessions
+        );
+        let game_args = args.clone();
+        session::host(
+            listener,
+            *sessions,
+            std::sync::Arc::new(move |app: &mut bevy::app::App, window| {
+                hosted(app, &game_args, window)
+            }),
+        );
+        logging::finish();
+        return Ok(());
+    }
+    let spectators = args
+        .spectate
+        .as_deref()
+        .map(spectate::Spectators::bind)
+        .transpose()?;
+
+    let mut app = bevy::app::App::new();
+    match &args.command {
+        None => local(&mut app, &args),
+        Some(cli::Command::Serve { address, players }) => {
+            let server = server::Server::bind(address, *players as usize)?;
+            println!(
+                "Listening on {}, each game starts once {} players join",
+                server.local_address()?,
+                players
+            );
+            serve(&mut app, &args, server);
+        }
+        Some(cli::Command::Join { address }) => {
+            let (client, size) = client::connect(address)?;
+            join(&mut app, client, size);
+        }
+        Some(cli::Command::Host { .. } | cli::Command::Watch { .. }) => {
+            unreachable!("handled above")
+        }
+    }
+    if let Some(spectators) = spectators {
+        app.add_plugins(spectate::plugin)
+            .insert_resource(spectators);
+    }
+    app.run();
+    // restore the terminal before printing anything held back for stderr
+    drop(app);
+    logging::finish();
+
+    Ok(())
+}
+
+/// A game played in this terminal.
+fn local(app: &mut bevy::app::App, args: &cli::Args) {
+    let size = frame::Size::choose(args.width, args.height, crossterm::terminal::size().ok());
+    app.add_plugins(bevy::MinimalPlugins.set(runloop()))
+        .add_plugins(terminal::plugin)
+        .add_plugins(mouse::plugin)
+        .insert_resource(mouse::Mouse {
+            enabled: args.mouse,
+            ..Default::default()
+        });
+    game(app, args, size);
+    dev_tools(app);
+    #[cfg(all(feature = "gamepad", target_os = "linux"))]
+    app.add_plugins(gamepad::plugin);
+}
+
+/// A game played over telnet in a `window` of that size, which leaves the
+/// config file alone and the developer tools out, since anyone can connect.
+/// `session` adds the terminal.
+fn hosted(app: &mut bevy::app::App, args: &cli::Args, window: (u16, u16)) {
+    let size = frame::Size::choose(args.width, args.height, Some(window));
+    app.add_plugins(bevy::MinimalPlugins.set(runloop()))
+        .add_plugins(terminal::drawing_plugin);
+    game(app, args, size);
+    app.insert_resource(config::Persist(false));
+}
+
+/// Everything but the terminal for a game played by whoever's in front
+/// of it.
+fn game(app: &mut bevy::app::App, args: &cli::Args, size: frame::Size) {
+    app.add_plugins(config::plugin)
+        .add_plugins(input::plugin)
+        .add_plugins(bindings_screen::plugin)
+        .add_plugins(app::plugin)
+        .add_plugins(app::local_plugin)
+        .add_plugins(title::plugin)
+        .add_plugins(highscore::plugin)
+        .add_plugins(settings::plugin);
+    simulation(app, args, size);
+}
+
+/// The console and debug overlay, for whoever's playing in this terminal.
+fn dev_tools(app: &mut bevy::app::App) {
+    app.add_plugins(console::plugin).add_plugins(debug::plugin);
+}
+
+/// A game run without a terminal, for players joining over the network.
+fn serve(app: &mut bevy::app::App, args: &cli::Args, server: server::Server) {
+    let size = frame::Size::choose(args.width, args.height, None);
+    app.add_plugins(bevy::MinimalPlugins.set(runloop()))
+        .add_plugins(config::plugin)
+        .add_plugins(app::plugin)
+        .add_plugins(server::plugin)
+        .insert_resource(server);
+    simulation(app, args, size);
+}
+
+/// Play on a server, which runs the game itself.
+fn join(app: &mut bevy::app::App, client: client::Client, size: frame::Size) {
+    app.add_plugins(bevy::MinimalPlugins.set(runloop()))
         .add_plugins(terminal::plugin)
+        .add_plugins(config::plugin)
+        .add_plugins(input::plugin)
+        .add_plugins(bindings_screen::plugin)
         .add_plugins(app::plugin)
+        .add_plugins(client::plugin)
+        .insert_resource(client)
+        .insert_resource(size);
+}
+
+/// The game itself, drawn into the buffer.
+fn simulation(app: &mut bevy::app::App, args: &cli::Args, size: frame::Size) {
+    app.add_plugins(bevy::transform::TransformPlugin)
+        .add_plugins(difficulty::plugin)
         .add_plugins(buffer::plugin)
+        .add_plugins(collider::plugin)
         .add_plugins(sprite::plugin)
+        .add_plugins(subcell::plugin)
+        .insert_resource(args.resolution)
+        .add_plugins(background::plugin)
         .add_plugins(level::plugin)
+        .insert_resource(args.mode)
+        .add_plugins(endless::plugin)
+        .add_plugins(score::plugin)
         .add_plugins(frame::plugin)
+        .insert_resource(size)
         .add_plugins(player::plugin)
         .add_plugins(weapon::plugin)
         .add_plugins(enemy::plugin)
-        .add_systems(bevy::app::Startup, startup)
-        .run();
-
-    Ok(())
+        .add_systems(bevy::app::Startup, startup);
 }
 
+/// Frames are paced by `app`, to the frame rate in the config.
 fn runloop() -> bevy::app::ScheduleRunnerPlugin {
-    bevy::app::ScheduleRunnerPlugin::run_loop(std::time::Duration::from_secs_f32(1.0 / 60.0))
+    bevy::app::ScheduleRunnerPlugin::run_loop(std::time::Duration::ZERO)
 }
 
-fn startup(mut writer: bevy::ecs::event::EventWriter<level::LevelEvent>) {
+fn startup() {
     log::info!("startup");
-    writer.send(level::LevelEvent::LevelStart);
 }
@@ -0,0 +1,71 @@
+/// Optional mouse control: the player steers toward the pointer and the
+/// left button fires.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Mouse {
+    pub enabled: bool,
+    /// Where the player is steering to, in frame coordinates.
+    pub pointer: Option<bevy::math::Vec2>,
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<Mouse>();
+    app.add_systems(bevy::app::Startup, capture.run_if(is_enabled));
+    app.add_systems(
+        bevy::app::PreUpdate,
+        dispatch
+            .after(crate::terminal::handle_events)
+            .run_if(is_enabled),
+    );
+}
+
+fn is_enabled(mouse: bevy::ecs::system::Res<Mouse>) -> bool {
+    mouse.enabled
+}
+
+fn capture(mut terminal: bevy::ecs::system::ResMut<crate::terminal::Terminal>) {
+    if let Err(e) = terminal.set_mouse_capture(true) {
+        log::error!("Couldn't enable mouse capture: {}", e);
+    }
+}
+
+fn dispatch(
+    mut reader: bevy::ecs::event::EventReader<crate::terminal::TerminalEvent>,
+    mut writer: bevy::ecs::event::EventWriter<crate::input::ActionEvent>,
+    mut mouse: bevy::ecs::system::ResMut<Mouse>,
+    play_area: bevy::ecs::system::Res<crate::terminal::PlayArea>,
+    screen: bevy::ecs::system::Res<crate::bindings_screen::BindingsScreen>,
+    console: bevy::ecs::system::Res<crate::console::Console>,
+) {
+    use crossterm::event::{MouseButton, MouseEventKind};
+
+    for event in reader.read() {
+        let crate::terminal::TerminalEvent::Mouse(event) = event else {
+            continue;
+        };
+        if screen.is_open() || console.is_open() {
+            continue;
+        }
+
+        let pressed = match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => Some(true),
+            MouseEventKind::Up(MouseButton::Left) => Some(false),
+            _ => None,
+        };
+        if let Some(pressed) = pressed {
+            writer.send(crate::input::ActionEvent {
+                action: crate::input::Action::Fire,
+                pressed,
+                player: 0,
+            });
+        }
+
+        if matches!(
+            event.kind,
+            MouseEventKind::Moved | MouseEventKind::Drag(_) | MouseEventKind::Down(_)
+        ) {
+            mouse.pointer = play_area.frame_point(event.column, event.row);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! What a server and the clients playing on it say to each other, as one
+//! JSON message per line over TCP.
+
+use std::io::{Read, Write};
+
+/// Where a server listens unless told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7777";
+/// Give up on a peer with this much still waiting to be sent to it.
+const MAX_BACKLOG: usize = 4 * 1024 * 1024;
+/// Longest message accepted, so a bad peer can't fill up memory.
+const MAX_MESSAGE: usize = 4 * 1024 * 1024;
+
+/// From a client to the server.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToServer {
+    Action {
+        action: crate::input::Action,
+        pressed: bool,
+    },
+}
+
+/// From the server to a client.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToClient {
+    /// Joined as `player`, on a play field of this size.
+    Welcome {
+        version: String,
+        player: usize,
+        width: usize,
+        height: usize,
+    },
+    /// Turned away, and why.
+    Refused {
+        reason: String,
+    },
+    /// Waiting for everyone to join before the game starts.
+    Waiting {
+        joined: usize,
+        needed: usize,
+    },
+    /// The cells which changed since the last frame, with the score and
+    /// each player's lives.
+    Frame {
+        changes: Vec<Change>,
+        score: crate::score::Score,
+        lives: Vec<u32>,
+    },
+    GameOver {
+        score: u32,
+    },
+}
+
+/// A cell which changed, by row and column.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Change {
+    pub row: usize,
+    pub col: usize,
+    /// An empty symbol continues the double width glyph to its left.
+    pub symbol: Option<String>,
+    pub fg: crate::buffer::Color,
+    pub bg: crate::buffer::Color,
+}
+
+/// The changes which turn `previous` into `next`, or all of `next` if
+/// there's nothing to compare it with.
+pub fn diff(previous: Option<&crate::buffer::Buffer>, next: &crate::buffer::Buffer) -> Vec<Change> {
+    let previous = previous.filter(|previous| previous.0.dim() == next.0.dim());
+    next.0
+        .indexed_iter()
+        .filter(|((row, col), cell)| {
+            previous.is_none_or(|previous| {
+                let old = &previous.0[[*row, *col]];
+                (old.character, old.fg, old.bg) != (cell.character, cell.fg, cell.bg)
+            })
+        })
+        .map(|((row, col), cell)| Change {
+            row,
+            col,
+            symbol: cell.character.map(|glyph| glyph.as_str().to_string()),
+            fg: cell.fg,
+            bg: cell.bg,
+        })
+        .collect()
+}
+
+/// Apply changes from `diff`, skipping any which don't fit.
+pub fn apply(buffer: &mut crate::buffer::Buffer, changes: &[Change]) {
+    for change in changes {
+        let Some(cell) = buffer.0.get_mut([change.row, change.col]) else {
+            continue;
+        };
+        cell.character = change.symbol.as_deref().and_then(crate::buffer::Glyph::new);
+        cell.fg = change.fg;
+        cell.bg = change.bg;
+    }
+}
+
+/// A TCP stream which never blocks the game, buffering what can't be sent
+/// or read in full yet.
+pub struct Connection {
+    stream: std::net::TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    pub fn new(stream: std::net::TcpStream) -> std::io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    pub fn peer(&self) -> String {
+        self.stream.peer_addr().map_or_else(
+            |_| "unknown peer".to_string(),
+            |address| address.to_string(),
+        )
+    }
+
+    /// Whether the other end has hung up.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Queue a message and send as much as the socket will take.
+    pub fn send(&mut self, message: &impl serde::Serialize) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.outgoing, message)?;
+        self.outgoing.push(b'\n');
+        if self.outgoing.len() > MAX_BACKLOG {
+            return Err(std::io::Error::other("peer has fallen too far behind"));
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// The messages which have arrived in full since last time.
+    pub fn receive<T: serde::de::DeserializeOwned>(&mut self) -> std::io::Result<Vec<T>> {
+        self.read()?;
+        std::iter::from_fn(|| self.next_message()).collect()
+    }
+
+    /// Wait up to `timeout` for the next message, leaving any after it for
+    /// `receive`.
+    pub fn wait<T: serde::de::DeserializeOwned>(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> std::io::Result<T> {
+        let start = std::time::Instant::now();
+        loop {
+            self.read()?;
+            if let Some(message) = self.next_message() {
+                return message;
+            }
+            if self.closed {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            if start.elapsed() > timeout {
+                return Err(std::io::ErrorKind::TimedOut.into());
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    /// Take in everything the socket has for us.
+    fn read(&mut self) -> std::io::Result<()> {
+        let mut chunk = [0; 4096];
+        while !self.closed {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.closed = true,
+                Ok(read) => self.incoming.extend_from_slice(&chunk[..read]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if self.incoming.len() > MAX_MESSAGE && !self.incoming.contains(&b'\n') {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "message too long",
+            ));
+        }
+        Ok(())
+    }
+
+    fn next_message<T: serde::de::DeserializeOwned>(&mut self) -> Option<std::io::Result<T>> {
+        let end = self.incoming.iter().position(|byte| *byte == b'\n')?;
+        let line: Vec<u8> = self.incoming.drain(..=end).collect();
+        Some(serde_json::from_slice(&line[..end]).map_err(std::io::Error::from))
+    }
+
+    /// Send what's left without blocking, and hang up once it's gone or
+    /// can't be sent. True once hung up, so call it again each frame until
+    /// then.
+    pub fn finish(&mut self) -> bool {
+        if self.flush().is_ok() && !self.outgoing.is_empty() {
+            return false;
+        }
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn diff_applies_to_previous_frame() {
+        use crate::buffer::{Buffer, Cell, Color};
+        let previous = Buffer(ndarray::Array2::from_elem((3, 4), Cell::default()));
+        let mut next = previous.clone();
+        next.0[[1, 2]].character = Some('x'.into());
+        next.0[[2, 0]].fg = Color::Red;
+        // depth alone isn't worth sending
+        next.0[[0, 0]].depth = 5.0;
+
+        let changes = diff(Some(&previous), &next);
+        assert_eq!(changes.len(), 2);
+        let mut copy = previous.clone();
+        apply(&mut copy, &changes);
+        assert_eq!(copy.0[[1, 2]].character, Some('x'.into()));
+        assert_eq!(copy.0[[2, 0]].fg, Color::Red);
+
+        assert_eq!(diff(None, &next).len(), 12);
+        assert!(diff(Some(&next), &next).is_empty());
+    }
+
+    #[test]
+    fn messages_cross_a_socket() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let mut client = Connection::new(client).unwrap();
+        let mut server = Connection::new(server).unwrap();
+
+        let message = ToServer::Action {
+            action: crate::input::Action::Fire,
+            pressed: true,
+        };
+        client.send(&message).unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+        assert_eq!(server.wait::<ToServer>(timeout).unwrap(), message);
+
+        server.send(&ToClient::GameOver { score: 42 }).unwrap();
+        assert!(server.finish());
+        assert_eq!(
+            client.wait::<ToClient>(timeout).unwrap(),
+            ToClient::GameOver { score: 42 }
+        );
+        assert!(client.wait::<ToClient>(timeout).is_err());
+        assert!(client.is_closed());
+    }
+}
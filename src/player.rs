@@ -1,10 +1,64 @@
+/// A ship, or its weapon, belonging to the player with this number,
+/// counting from 0.
+#[derive(bevy::ecs::component::Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Player(pub usize);
+
+/// How many ships take part, picked on the title screen.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Players(pub usize);
+
+impl Default for Players {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// As many players as fit around one keyboard.
+pub const MAX_PLAYERS: usize = 2;
+/// Lives each ship starts with.
+pub const LIVES: u32 = 3;
+/// Seconds a ship can't be hit after losing a life.
+const RECOVERY_TIME: f32 = 2.0;
+/// Seconds a recovering ship spends shown, then hidden.
+const BLINK_TIME: f32 = 0.1;
+
+/// Each player's sprite and colour, in turn.
+const SHIPS: [(&str, crate::buffer::Color); 4] = [
+    ("]o>", crate::buffer::Color::Reset),
+    ("]x>", crate::buffer::Color::LightCyan),
+    ("]o>", crate::buffer::Color::LightYellow),
+    ("]x>", crate::buffer::Color::LightMagenta),
+];
+
+/// Enemies pass straight through a player with this.
 #[derive(bevy::ecs::component::Component)]
-pub struct Player;
+pub struct Invulnerable;
+
+/// Enemies pass through a ship which has just lost a life, while it
+/// blinks.
+#[derive(bevy::ecs::component::Component)]
+pub struct Recovering(bevy::time::Timer);
+
+impl Default for Recovering {
+    fn default() -> Self {
+        Self(bevy::time::Timer::from_seconds(
+            RECOVERY_TIME,
+            bevy::time::TimerMode::Once,
+        ))
+    }
+}
 
 #[derive(bevy::ecs::component::Component)]
 pub struct PlayerState {
     speed: f32,
     state: u8,
+    lives: u32,
+}
+
+impl PlayerState {
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
 }
 
 const MOVING_LEFT: u8 = 0b0000_0001;
@@ -12,6 +66,17 @@ const MOVING_RIGHT: u8 = 0b0000_0010;
 const MOVING_UP: u8 = 0b0000_0100;
 const MOVING_DOWN: u8 = 0b0000_1000;
 
+fn movement(action: crate::input::Action) -> Option<u8> {
+    use crate::input::Action::*;
+    match action {
+        MoveUp => Some(MOVING_UP),
+        MoveDown => Some(MOVING_DOWN),
+        MoveLeft => Some(MOVING_LEFT),
+        MoveRight => Some(MOVING_RIGHT),
+        _ => None,
+    }
+}
+
 fn direction(state: u8) -> bevy::math::f32::Vec3 {
     let component = |flag: u8| -> f32 {
         if state & flag > 0 {
@@ -20,186 +85,300 @@ fn direction(state: u8) -> bevy::math::f32::Vec3 {
             0.0
         }
     };
-    match bevy::math::f32::Vec3::new(
+    bevy::math::f32::Vec3::new(
         component(MOVING_RIGHT) - component(MOVING_LEFT),
         component(MOVING_DOWN) - component(MOVING_UP),
         0.0,
     )
     .try_normalize()
-    {
-        Some(v) => v,
-        None => bevy::math::f32::Vec3::default(),
+    .unwrap_or_default()
+}
+
+/// Move from `position` toward `goal` by at most `max_step`.
+fn steer(
+    position: bevy::math::f32::Vec3,
+    goal: bevy::math::f32::Vec3,
+    max_step: f32,
+) -> bevy::math::f32::Vec3 {
+    let offset = goal - position;
+    if offset.length() <= max_step {
+        goal
+    } else {
+        position + offset.normalize() * max_step
     }
 }
 
 pub fn plugin(app: &mut bevy::app::App) {
     use bevy::ecs::schedule::{
-        common_conditions::{any_with_component, not},
+        common_conditions::{any_with_component, in_state, resource_exists},
         IntoSystemConfigs,
     };
 
+    app.init_resource::<Players>();
     app.add_systems(
         bevy::app::Update,
-        spawn
-            .run_if(not(any_with_component::<PlayerState>))
-            .run_if(any_with_component::<crate::frame::Frame>),
+        spawn.run_if(any_with_component::<crate::frame::Frame>),
     );
     app.add_systems(
         bevy::app::Update,
         update.run_if(any_with_component::<PlayerState>),
     );
+    app.add_systems(
+        bevy::app::Update,
+        recover.run_if(any_with_component::<Recovering>),
+    );
+    app.add_systems(
+        bevy::app::PostUpdate,
+        update_hud
+            .run_if(in_state(crate::app::Screen::Playing))
+            .run_if(resource_exists::<crate::terminal::Hud>),
+    );
+    app.add_systems(
+        bevy::ecs::schedule::OnExit(crate::app::Screen::Playing),
+        clear_hud.run_if(resource_exists::<crate::terminal::Hud>),
+    );
 }
 
+/// Take a life from the ship, which leaves the game once it has none left.
+pub fn crash(
+    commands: &mut bevy::ecs::system::Commands,
+    entity: bevy::ecs::entity::Entity,
+    player: Player,
+    state: &mut PlayerState,
+) {
+    use bevy::hierarchy::DespawnRecursiveExt;
+
+    state.lives = state.lives.saturating_sub(1);
+    if state.lives == 0 {
+        log::info!("Player {} is out", player.0 + 1);
+        commands.entity(entity).despawn_recursive();
+    } else {
+        log::info!("Player {} lost a life, {} left", player.0 + 1, state.lives);
+        commands.entity(entity).insert(Recovering::default());
+    }
+}
+
+/// Put the ships in each new frame, spread down its left edge.
 fn spawn(
     mut commands: bevy::ecs::system::Commands,
+    players: bevy::ecs::system::Res<Players>,
     frame_query: bevy::ecs::system::Query<
         (bevy::ecs::entity::Entity, &crate::collider::Collider),
-        bevy::ecs::query::With<crate::frame::Frame>,
+        bevy::ecs::query::Added<crate::frame::Frame>,
     >,
 ) {
-    let Ok((frame, frame_collider)) = frame_query.get_single() else {
-        log::error!("Couldn't get a frame instance");
-        return;
-    };
+    use bevy::hierarchy::BuildChildren;
 
-    log::info!("spawning player");
+    for (frame, frame_collider) in &frame_query {
+        for number in 0..players.0 {
+            log::info!("spawning player {}", number + 1);
 
-    use bevy::hierarchy::BuildChildren;
+            let player = Player(number);
+            let weapon = commands
+                .spawn((
+                    player,
+                    crate::weapon::Weapon::new(0.3),
+                    bevy::transform::TransformBundle::from_transform(
+                        bevy::transform::components::Transform::from_translation(
+                            bevy::math::f32::Vec3::new(3.0, 0.0, 0.0),
+                        ),
+                    ),
+                ))
+                .id();
 
-    let weapon = commands
-        .spawn((
-            Player,
-            crate::weapon::Weapon::new(0.3),
-            bevy::transform::TransformBundle::from_transform(
-                bevy::transform::components::Transform::from_translation(
-                    bevy::math::f32::Vec3::new(3.0, 0.0, 0.0),
-                ),
-            ),
-        ))
-        .id();
-
-    commands
-        .spawn((
-            Player,
-            PlayerState {
-                speed: 20.0,
-                state: 0,
-            },
-            crate::sprite::Sprite {
-                buffer: crate::buffer::Buffer(ndarray::array![[
-                    crate::buffer::Cell {
-                        character: Some(']'),
-                        ..Default::default()
-                    },
-                    crate::buffer::Cell {
-                        character: Some('o'),
-                        ..Default::default()
-                    },
-                    crate::buffer::Cell {
-                        character: Some('>'),
-                        ..Default::default()
+            let (lines, colour) = SHIPS[number % SHIPS.len()];
+            let mut sprite =
+                crate::sprite::Sprite::from_lines(&[lines]).expect("valid player sprite");
+            sprite.buffer.0.iter_mut().for_each(|cell| cell.fg = colour);
+            let y = frame_collider.y * (number + 1) as f32 / (players.0 + 1) as f32;
+
+            commands
+                .spawn((
+                    player,
+                    PlayerState {
+                        speed: 20.0,
+                        state: 0,
+                        lives: LIVES,
                     },
-                ]]),
-            },
-            bevy::transform::TransformBundle::from_transform(
-                bevy::transform::components::Transform::from_translation(
-                    bevy::math::f32::Vec3::new(0.0, frame_collider.y / 2.0, 0.0),
-                ),
-            ),
-            crate::collider::Collider::new(3.0, 1.0),
-        ))
-        .push_children(&[weapon])
-        .set_parent(frame);
+                    sprite.clone(),
+                    bevy::transform::TransformBundle::from_transform(
+                        bevy::transform::components::Transform::from_translation(
+                            bevy::math::f32::Vec3::new(0.0, y.floor(), 0.0),
+                        ),
+                    ),
+                    crate::collider::Collider::new(3.0, 1.0),
+                    crate::collider::CollisionLayers::new(
+                        crate::collider::layer::PLAYER,
+                        crate::collider::layer::ENEMY,
+                    ),
+                    crate::collider::Mask::from_sprite(&sprite),
+                ))
+                .push_children(&[weapon])
+                .set_parent(frame);
+        }
+    }
 }
 
+/// Players, which are never the frame itself.
+type PlayerOnly = (
+    bevy::ecs::query::Without<crate::frame::Frame>,
+    bevy::ecs::query::With<Player>,
+);
+
 fn update(
     time: bevy::ecs::system::Res<bevy::time::Time>,
-    mut reader: bevy::ecs::event::EventReader<crate::terminal::TerminalEvent>,
-    mut weapon_query: bevy::ecs::system::Query<
-        &mut crate::weapon::Weapon,
-        (
-            bevy::ecs::query::Without<crate::frame::Frame>,
-            bevy::ecs::query::With<Player>,
-        ),
-    >,
+    mut mouse: Option<bevy::ecs::system::ResMut<crate::mouse::Mouse>>,
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut weapon_query: bevy::ecs::system::Query<(&mut crate::weapon::Weapon, &Player), PlayerOnly>,
     mut query: bevy::ecs::system::Query<
         (
             &mut bevy::transform::components::Transform,
             &mut PlayerState,
             &crate::collider::Collider,
+            &Player,
         ),
-        (
-            bevy::ecs::query::Without<crate::frame::Frame>,
-            bevy::ecs::query::With<Player>,
-        ),
+        PlayerOnly,
     >,
     frame_query: bevy::ecs::system::Query<
         &crate::collider::Collider,
         bevy::ecs::query::With<crate::frame::Frame>,
     >,
 ) {
-    let Ok((mut transform, mut player_state, collider)) = query.get_single_mut() else {
-        log::error!("Couldn't get a unique player instance.");
-        return;
-    };
-
-    let Ok(mut weapon) = weapon_query.get_single_mut()  else {
-        log::error!("Couldn't get a unique weapon instance.");
+    let Ok(frame_collider) = frame_query.get_single() else {
+        log::error!("Couldn't get a unique frame instance");
         return;
     };
 
-    for event in reader.read() {
-        let crate::terminal::TerminalEvent::Key(key) = event else {
-            continue;
-        };
+    let events: Vec<_> = reader.read().copied().collect();
 
-        use crossterm::event::KeyCode::*;
-        use crossterm::event::KeyEventKind::*;
+    for (mut weapon, player) in &mut weapon_query {
+        for event in &events {
+            if event.player == player.0 && event.action == crate::input::Action::Fire {
+                weapon.trigger(event.pressed);
+            }
+        }
+    }
 
-        let mut update_move_state = |state: u8| match &key.kind {
-            Press => {
+    for (mut transform, mut player_state, collider, player) in &mut query {
+        for event in events.iter().filter(|event| event.player == player.0) {
+            let Some(state) = movement(event.action) else {
+                continue;
+            };
+            if event.pressed {
                 player_state.state |= state;
-            }
-            Release => {
+            } else {
                 player_state.state &= !state;
             }
-            _ => {}
-        };
+        }
 
-        let mut update_weapon_state = || match &key.kind {
-            Press => {
-                weapon.trigger(true);
+        // only the first player can steer with the mouse
+        let mouse = mouse.as_mut().filter(|_| player.0 == 0);
+        let pointer = mouse.as_ref().and_then(|mouse| mouse.pointer);
+        if player_state.state != 0 {
+            if let Some(mouse) = mouse {
+                // the keys take over until the mouse moves again
+                mouse.pointer = None;
             }
-            Release => {
-                weapon.trigger(false);
-            }
-            _ => {}
-        };
-
-        match &key.code {
-            Char('w') => update_move_state(MOVING_UP),
-            Char('a') => update_move_state(MOVING_LEFT),
-            Char('s') => update_move_state(MOVING_DOWN),
-            Char('d') => update_move_state(MOVING_RIGHT),
-            Char(' ') => update_weapon_state(),
-            _ => {}
+            transform.translation +=
+                player_state.speed * direction(player_state.state) * time.delta_seconds();
+        } else if let Some(pointer) = pointer {
+            // centre the ship on the pointer
+            let size = bevy::math::Vec2::new(collider.x, collider.y);
+            let goal = pointer - (size - bevy::math::Vec2::ONE) / 2.0;
+            transform.translation = steer(
+                transform.translation,
+                goal.extend(transform.translation.z),
+                player_state.speed * time.delta_seconds(),
+            );
+        }
+
+        transform.translation.x = transform
+            .translation
+            .x
+            .clamp(0.0, frame_collider.x - collider.x);
+        transform.translation.y = transform
+            .translation
+            .y
+            .clamp(0.0, frame_collider.y - collider.y);
+    }
+}
+
+fn recover(
+    mut commands: bevy::ecs::system::Commands,
+    time: bevy::ecs::system::Res<bevy::time::Time>,
+    mut query: bevy::ecs::system::Query<(bevy::ecs::entity::Entity, &mut Recovering)>,
+) {
+    for (entity, mut recovering) in &mut query {
+        recovering.0.tick(time.delta());
+        let mut entity = commands.entity(entity);
+        if recovering.0.finished() {
+            entity.remove::<(Recovering, crate::sprite::Hidden)>();
+        } else if (recovering.0.elapsed_secs() / BLINK_TIME) as u32 % 2 == 1 {
+            entity.insert(crate::sprite::Hidden);
+        } else {
+            entity.remove::<crate::sprite::Hidden>();
         }
     }
+}
 
-    transform.translation +=
-        player_state.speed * direction(player_state.state) * time.delta_seconds();
+/// Each player's lives and points, along the bottom of the play area's
+/// border.
+pub fn hud(players: usize, lives: impl Fn(usize) -> u32, score: &crate::score::Score) -> String {
+    use itertools::Itertools;
+    (0..players)
+        .map(|player| {
+            format!(
+                "P{} {:<width$} {}",
+                player + 1,
+                "♥".repeat(lives(player) as usize),
+                score.player(player),
+                width = LIVES as usize
+            )
+        })
+        .join("   ")
+}
 
-    let Ok(frame_collider) = frame_query.get_single() else {
-        log::error!("Couldn't get a unique frame instance");
-        return;
+fn update_hud(
+    players: bevy::ecs::system::Res<Players>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    query: bevy::ecs::system::Query<(&Player, &PlayerState)>,
+    mut shown: bevy::ecs::system::ResMut<crate::terminal::Hud>,
+) {
+    let lives = |number| {
+        query
+            .iter()
+            .find(|(player, _)| player.0 == number)
+            .map_or(0, |(_, state)| state.lives)
     };
+    shown.0 = Some(hud(players.0, lives, &score));
+}
+
+fn clear_hud(mut hud: bevy::ecs::system::ResMut<crate::terminal::Hud>) {
+    hud.0 = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
 
-    transform.translation.x = transform
-        .translation
-        .x
-        .clamp(0.0, frame_collider.x - collider.x);
-    transform.translation.y = transform
-        .translation
-        .y
-        .clamp(0.0, frame_collider.y - collider.y);
+    #[test]
+    fn steer_stops_at_goal() {
+        use bevy::math::f32::Vec3;
+        let position = Vec3::new(0.0, 0.0, 0.0);
+        let goal = Vec3::new(3.0, 4.0, 0.0);
+        assert_eq!(steer(position, goal, 1.0), Vec3::new(0.6, 0.8, 0.0));
+        assert_eq!(steer(position, goal, 10.0), goal);
+    }
+
+    #[test]
+    fn hud_lines_up_players() {
+        let mut score = crate::score::Score::default();
+        score.add_kill(1);
+        assert_eq!(
+            hud(2, |player| [3, 1][player], &score),
+            "P1 ♥♥♥ 0   P2 ♥   100"
+        );
+        assert_eq!(hud(1, |_| 0, &score), "P1     0");
+    }
 }
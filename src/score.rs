@@ -0,0 +1,82 @@
+/// Points awarded for each enemy destroyed.
+pub const KILL_SCORE: u32 = 100;
+
+#[derive(
+    bevy::ecs::system::Resource,
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct Score {
+    /// Enemies destroyed by each player, by player number.
+    pub kills: Vec<u32>,
+    pub distance: f32,
+}
+
+impl Score {
+    /// The score of the whole team.
+    pub fn total(&self) -> u32 {
+        self.distance.max(0.0) as u32 + self.kills.iter().sum::<u32>() * KILL_SCORE
+    }
+
+    /// Points for the enemies `player` destroyed.
+    pub fn player(&self, player: usize) -> u32 {
+        self.kills.get(player).copied().unwrap_or_default() * KILL_SCORE
+    }
+
+    pub fn add_kill(&mut self, player: usize) {
+        if self.kills.len() <= player {
+            self.kills.resize(player + 1, 0);
+        }
+        self.kills[player] += 1;
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::any_with_component, IntoSystemConfigs};
+
+    app.init_resource::<Score>();
+    app.add_systems(
+        bevy::app::Update,
+        update_distance.run_if(any_with_component::<crate::frame::Frame>),
+    );
+}
+
+fn update_distance(
+    mut score: bevy::ecs::system::ResMut<Score>,
+    frame_query: bevy::ecs::system::Query<
+        &bevy::transform::components::Transform,
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    let Ok(frame_transform) = frame_query.get_single() else {
+        log::error!("Couldn't get unique frame instance");
+        return;
+    };
+
+    score.distance = frame_transform.translation.x;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn total_is_distance_plus_kills() {
+        let mut score = Score {
+            distance: 42.7,
+            ..Default::default()
+        };
+        score.add_kill(1);
+        score.add_kill(0);
+        score.add_kill(1);
+        assert_eq!(score.total(), 42 + 3 * KILL_SCORE);
+        assert_eq!(score.player(0), KILL_SCORE);
+        assert_eq!(score.player(1), 2 * KILL_SCORE);
+        assert_eq!(score.player(2), 0);
+    }
+}
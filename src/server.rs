@@ -0,0 +1,466 @@
+use crate::app::Screen;
+use crate::input::Action;
+use crate::net::{Connection, ToClient, ToServer};
+
+/// How long a connection being hung up on has to take what's left for it.
+const FINISH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A player connected to the server.
+struct Client {
+    connection: Connection,
+    player: usize,
+    /// The last frame sent, which the next is sent as changes to.
+    sent: Option<crate::buffer::Buffer>,
+    /// Failed to send, so drop at the next chance.
+    gone: bool,
+}
+
+/// Runs the game for players joining over TCP. Between games the server
+/// sits on the title screen as a lobby, and starts once `needed` players
+/// have joined.
+#[derive(bevy::ecs::system::Resource)]
+pub struct Server {
+    listener: std::net::TcpListener,
+    needed: usize,
+    clients: Vec<Client>,
+    /// Connections being hung up on once they've been sent what's left,
+    /// and when to give up on that.
+    leaving: Vec<(Connection, std::time::Instant)>,
+}
+
+impl Server {
+    pub fn bind(address: &str, needed: usize) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            needed,
+            clients: Vec::new(),
+            leaving: Vec::new(),
+        })
+    }
+
+    pub fn local_address(&self) -> std::io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    fn broadcast(&mut self, message: &ToClient) {
+        for client in &mut self.clients {
+            if let Err(e) = client.connection.send(message) {
+                log::warn!("Player {} dropped: {}", client.player + 1, e);
+                client.gone = true;
+            }
+        }
+    }
+
+    /// Hang up on `connection` once it's been sent what's left, without
+    /// holding up the game.
+    fn hang_up(&mut self, mut connection: Connection) {
+        if !connection.finish() {
+            self.leaving
+                .push((connection, std::time::Instant::now() + FINISH_TIMEOUT));
+        }
+    }
+
+    fn waiting(&self) -> ToClient {
+        ToClient::Waiting {
+            joined: self.clients.len(),
+            needed: self.needed,
+        }
+    }
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::in_state, IntoSystemConfigs};
+
+    app.add_systems(
+        bevy::app::PreUpdate,
+        (
+            finish_leaving,
+            accept,
+            receive.after(accept),
+            start.after(receive),
+        ),
+    );
+    app.add_systems(
+        bevy::app::Last,
+        broadcast
+            .run_if(in_state(Screen::Playing))
+            .after(crate::subcell::merge),
+    );
+    app.add_systems(bevy::ecs::schedule::OnEnter(Screen::NameEntry), end_game);
+}
+
+/// Finish sending to the connections being hung up on, giving up on any
+/// which take too long.
+fn finish_leaving(mut server: bevy::ecs::system::ResMut<Server>) {
+    let now = std::time::Instant::now();
+    server
+        .leaving
+        .retain_mut(|(connection, deadline)| !connection.finish() && now < *deadline);
+}
+
+/// Let players in while the lobby has room.
+fn accept(
+    mut server: bevy::ecs::system::ResMut<Server>,
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    screen: bevy::ecs::system::Res<bevy::ecs::schedule::State<Screen>>,
+) {
+    loop {
+        let stream = match server.listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+            Err(e) => {
+                log::error!("Failed to accept a connection: {}", e);
+                break;
+            }
+        };
+        let mut connection = match Connection::new(stream) {
+            Ok(connection) => connection,
+            Err(e) => {
+                log::error!("Failed to set up a connection: {}", e);
+                continue;
+            }
+        };
+
+        let lobby = *screen.get() == Screen::Title;
+        if !lobby || server.clients.len() >= server.needed {
+            log::info!("Turned away {}, the game has started", connection.peer());
+            let _ = connection.send(&ToClient::Refused {
+                reason: "the game has already started".to_string(),
+            });
+            server.hang_up(connection);
+            continue;
+        }
+
+        // take the lowest number free
+        let player = (0..)
+            .find(|number| server.clients.iter().all(|client| client.player != *number))
+            .expect("a free player number");
+        log::info!("{} joined as player {}", connection.peer(), player + 1);
+        let welcome = ToClient::Welcome {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            player,
+            width: size.width,
+            height: size.height,
+        };
+        if let Err(e) = connection.send(&welcome) {
+            log::warn!("Failed to welcome {}: {}", connection.peer(), e);
+            continue;
+        }
+        server.clients.push(Client {
+            connection,
+            player,
+            sent: None,
+            gone: false,
+        });
+        let waiting = server.waiting();
+        server.broadcast(&waiting);
+    }
+}
+
+/// Start the game once the lobby is full, counting only the players who
+/// are still there after `receive` has dropped any who left.
+fn start(
+    server: bevy::ecs::system::Res<Server>,
+    screen: bevy::ecs::system::Res<bevy::ecs::schedule::State<Screen>>,
+    mut players: bevy::ecs::system::ResMut<crate::player::Players>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    if *screen.get() == Screen::Title && server.clients.len() == server.needed {
+        log::info!("Starting a game for {} players", server.needed);
+        players.0 = server.needed;
+        next_screen.set(Screen::Playing);
+    }
+}
+
+/// Turn what the players send into their actions, and drop any who have
+/// gone.
+#[allow(clippy::too_many_arguments)]
+fn receive(
+    mut commands: bevy::ecs::system::Commands,
+    mut server: bevy::ecs::system::ResMut<Server>,
+    mut writer: bevy::ecs::event::EventWriter<crate::input::ActionEvent>,
+    screen: bevy::ecs::system::Res<bevy::ecs::schedule::State<Screen>>,
+    ships: bevy::ecs::system::Query<(
+        bevy::ecs::entity::Entity,
+        &crate::player::Player,
+        &crate::player::PlayerState,
+    )>,
+    mut level_events: bevy::ecs::event::EventWriter<crate::level::LevelEvent>,
+) {
+    use bevy::hierarchy::DespawnRecursiveExt;
+
+    let mut dropped = Vec::new();
+    for client in &mut server.clients {
+        match client.connection.receive::<ToServer>() {
+            Ok(messages) => {
+                for ToServer::Action { action, pressed } in messages {
+                    // the rest only make sense to the player's own terminal
+                    if matches!(
+                        action,
+                        Action::MoveUp
+                            | Action::MoveDown
+                            | Action::MoveLeft
+                            | Action::MoveRight
+                            | Action::Fire
+                            | Action::Bomb
+                    ) {
+                        writer.send(crate::input::ActionEvent {
+                            action,
+                            pressed,
+                            player: client.player,
+                        });
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("Player {} dropped: {}", client.player + 1, e);
+                client.gone = true;
+            }
+        }
+        if client.connection.is_closed() && !client.gone {
+            log::info!("Player {} left", client.player + 1);
+            client.gone = true;
+        }
+        if client.gone {
+            dropped.push(client.player);
+        }
+    }
+    if dropped.is_empty() {
+        return;
+    }
+    server.clients.retain(|client| !client.gone);
+
+    if *screen.get() != Screen::Playing {
+        let waiting = server.waiting();
+        server.broadcast(&waiting);
+        return;
+    }
+
+    for (entity, player, _) in &ships {
+        if dropped.contains(&player.0) {
+            // leave nothing held down
+            writer.send_batch(Action::ALL.map(|action| crate::input::ActionEvent {
+                action,
+                pressed: false,
+                player: player.0,
+            }));
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    let playing = ships
+        .iter()
+        .any(|(_, player, state)| !dropped.contains(&player.0) && state.lives() > 0);
+    if !playing {
+        log::info!("Nobody left playing, ending the game");
+        level_events.send(crate::level::LevelEvent::LevelEnd);
+    }
+}
+
+/// Send each player what changed on the play field, the score and lives.
+fn broadcast(
+    mut server: bevy::ecs::system::ResMut<Server>,
+    buffer: bevy::ecs::system::Res<crate::buffer::Buffer>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    players: bevy::ecs::system::Res<crate::player::Players>,
+    ships: bevy::ecs::system::Query<(&crate::player::Player, &crate::player::PlayerState)>,
+) {
+    let lives: Vec<u32> = (0..players.0)
+        .map(|number| {
+            ships
+                .iter()
+                .find(|(player, _)| player.0 == number)
+                .map_or(0, |(_, state)| state.lives())
+        })
+        .collect();
+
+    for client in &mut server.clients {
+        let frame = ToClient::Frame {
+            changes: crate::net::diff(client.sent.as_ref(), &buffer),
+            score: score.clone(),
+            lives: lives.clone(),
+        };
+        match client.connection.send(&frame) {
+            Ok(()) => match &mut client.sent {
+                Some(sent) => sent.clone_from(&buffer),
+                None => client.sent = Some(buffer.clone()),
+            },
+            Err(e) => {
+                log::warn!("Player {} dropped: {}", client.player + 1, e);
+                client.gone = true;
+            }
+        }
+    }
+}
+
+/// Tell everyone the final score, then open the lobby for the next game.
+fn end_game(
+    mut server: bevy::ecs::system::ResMut<Server>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    log::info!("Game over with {} points", score.total());
+    server.broadcast(&ToClient::GameOver {
+        score: score.total(),
+    });
+    for client in std::mem::take(&mut server.clients) {
+        server.hang_up(client.connection);
+    }
+    next_screen.set(Screen::Title);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A game for two run the way `shellaga serve` runs it, on a port of
+    /// its own.
+    fn serve() -> (bevy::app::App, std::net::SocketAddr) {
+        let server = Server::bind("127.0.0.1:0", 2).unwrap();
+        let address = server.local_address().unwrap();
+        let mut app = bevy::app::App::new();
+        app.add_plugins(bevy::MinimalPlugins)
+            .insert_resource(crate::config::Config::default())
+            .add_plugins(crate::app::plugin)
+            .add_plugins(plugin)
+            .insert_resource(server)
+            .add_plugins(bevy::transform::TransformPlugin)
+            .add_plugins(crate::difficulty::plugin)
+            .add_plugins(crate::buffer::plugin)
+            .add_plugins(crate::collider::plugin)
+            .add_plugins(crate::sprite::plugin)
+            .add_plugins(crate::subcell::plugin)
+            .init_resource::<crate::subcell::Resolution>()
+            .add_plugins(crate::background::plugin)
+            .add_plugins(crate::level::plugin)
+            .init_resource::<crate::level::Mode>()
+            .add_plugins(crate::endless::plugin)
+            .add_plugins(crate::score::plugin)
+            .add_plugins(crate::frame::plugin)
+            .init_resource::<crate::frame::Size>()
+            .add_plugins(crate::player::plugin)
+            .add_plugins(crate::weapon::plugin)
+            .add_plugins(crate::enemy::plugin);
+        (app, address)
+    }
+
+    fn join(address: std::net::SocketAddr) -> Connection {
+        Connection::new(std::net::TcpStream::connect(address).unwrap()).unwrap()
+    }
+
+    /// Run frames until `done`, failing if that takes too long.
+    fn run_until(app: &mut bevy::app::App, mut done: impl FnMut(&mut bevy::app::App) -> bool) {
+        for _ in 0..500 {
+            app.update();
+            if done(app) {
+                return;
+            }
+        }
+        panic!("gave up waiting");
+    }
+
+    fn screen(app: &bevy::app::App) -> Screen {
+        *app.world
+            .resource::<bevy::ecs::schedule::State<Screen>>()
+            .get()
+    }
+
+    fn ships(app: &mut bevy::app::App) -> Vec<usize> {
+        let mut ships: Vec<_> = app
+            .world
+            .query::<(&crate::player::Player, &crate::player::PlayerState)>()
+            .iter(&app.world)
+            .map(|(player, _)| player.0)
+            .collect();
+        ships.sort();
+        ships
+    }
+
+    #[test]
+    fn game_runs_until_everyone_leaves() {
+        let (mut app, address) = serve();
+        let mut first = join(address);
+        let mut second = join(address);
+        run_until(&mut app, |app| ships(app) == [0, 1]);
+        assert_eq!(screen(&app), Screen::Playing);
+
+        let timeout = std::time::Duration::from_secs(5);
+        assert!(matches!(
+            first.wait::<ToClient>(timeout).unwrap(),
+            ToClient::Welcome { player: 0, .. }
+        ));
+        assert!(matches!(
+            second.wait::<ToClient>(timeout).unwrap(),
+            ToClient::Welcome { player: 1, .. }
+        ));
+
+        // the lobby is closed once the game has started
+        let mut late = join(address);
+        run_until(&mut app, |_| true);
+        assert!(matches!(
+            late.wait::<ToClient>(timeout).unwrap(),
+            ToClient::Refused { .. }
+        ));
+
+        // a player leaving takes their ship, and the game goes on
+        drop(first);
+        run_until(&mut app, |app| ships(app) == [1]);
+        assert_eq!(screen(&app), Screen::Playing);
+
+        drop(second);
+        run_until(&mut app, |app| screen(app) == Screen::Title);
+        assert!(app.world.resource::<Server>().clients.is_empty());
+    }
+
+    #[test]
+    fn leaving_as_the_lobby_fills_holds_the_game() {
+        let (mut app, address) = serve();
+        let first = join(address);
+        run_until(&mut app, |app| {
+            app.world.resource::<Server>().clients.len() == 1
+        });
+
+        // gone before the frame which lets the second player in
+        let mut second = join(address);
+        drop(first);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(screen(&app), Screen::Title);
+        assert_eq!(app.world.resource::<Server>().clients.len(), 1);
+        assert!(ships(&mut app).is_empty());
+
+        // the next player to join starts it
+        let mut third = join(address);
+        run_until(&mut app, |app| ships(app) == [0, 1]);
+        let timeout = std::time::Duration::from_secs(5);
+        assert!(matches!(
+            second.wait::<ToClient>(timeout).unwrap(),
+            ToClient::Welcome { player: 1, .. }
+        ));
+        assert!(matches!(
+            third.wait::<ToClient>(timeout).unwrap(),
+            ToClient::Welcome { player: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn servers_can_be_spectated() {
+        let (mut app, _) = serve();
+        let spectators = crate::spectate::Spectators::bind("127.0.0.1:0").unwrap();
+        let address = spectators.local_address();
+        app.add_plugins(crate::spectate::plugin)
+            .insert_resource(spectators);
+        app.update();
+
+        let mut watcher = std::net::TcpStream::connect(address).unwrap();
+        watcher
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        app.update();
+        let mut chunk = [0; 1024];
+        assert!(std::io::Read::read(&mut watcher, &mut chunk).unwrap() > 0);
+    }
+}
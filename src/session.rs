@@ -0,0 +1,210 @@
+//! Hosts a game of its own for everyone who connects, over telnet, drawn
+//! on their terminal rather than this one.
+
+use std::io::{Read, Write};
+
+/// Where sessions are hosted unless told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:2323";
+/// How long a client has to report its window size before it's assumed.
+const NEGOTIATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+/// The window size assumed for clients which don't report one.
+const DEFAULT_WINDOW: (u16, u16) = (80, 24);
+/// How long to wait on a client which isn't taking what it's sent.
+const WRITE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Builds a player's game, given the window size their client reported.
+pub type Build = dyn Fn(&mut bevy::app::App, (u16, u16)) + Send + Sync;
+
+/// A session's place among those running, given up when its thread ends,
+/// even by panicking.
+struct Slot(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Accept connections on `listener` forever, running each in a game of its
+/// own on a thread of its own, up to `limit` at once.
+pub fn host(listener: std::net::TcpListener, limit: usize, build: std::sync::Arc<Build>) {
+    let running = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::error!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let peer = stream.peer_addr().map_or_else(
+            |_| "unknown peer".to_string(),
+            |address| address.to_string(),
+        );
+
+        if running.load(std::sync::atomic::Ordering::SeqCst) >= limit {
+            log::info!("Turned away {}, {} sessions running", peer, limit);
+            let _ = stream.write_all(b"Too many players right now, try again later.\r\n");
+            continue;
+        }
+        running.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let slot = Slot(std::sync::Arc::clone(&running));
+
+        let build = std::sync::Arc::clone(&build);
+        let spawned = std::thread::Builder::new()
+            .name(format!("session {}", peer))
+            .spawn(move || {
+                let _slot = slot;
+                log::info!("Session for {} started", peer);
+                match run(stream, &*build) {
+                    Ok(()) => log::info!("Session for {} ended", peer),
+                    Err(e) => log::warn!("Session for {} failed: {}", peer, e),
+                }
+            });
+        if let Err(e) = spawned {
+            log::error!("Failed to start a session: {}", e);
+        }
+    }
+}
+
+/// Play a game over `stream` until the player quits or hangs up.
+fn run(mut stream: std::net::TcpStream, build: &Build) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.write_all(&crate::telnet::NEGOTIATION)?;
+
+    let mut decoder = crate::telnet::Decoder::default();
+    let Some(window) = negotiate(&mut stream, &mut decoder)? else {
+        log::info!("Player hung up before the game started");
+        return Ok(());
+    };
+    log::info!("Window is {}x{}", window.0, window.1);
+
+    // reads block, so they get a thread of their own
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut reader = stream.try_clone()?;
+    std::thread::spawn(move || {
+        let mut chunk = [0; 1024];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if sender.send(chunk[..read].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let terminal =
+        crate::terminal::Terminal::remote(Box::new(stream.try_clone()?), window.0, window.1)?;
+    let mut app = bevy::app::App::new();
+    build(&mut app, window);
+    app.insert_resource(terminal)
+        .insert_resource(Session {
+            decoder,
+            incoming: std::sync::Mutex::new(receiver),
+        })
+        .add_plugins(plugin);
+    // so a window too small for the game pauses it from the start
+    app.world
+        .send_event(crate::terminal::TerminalEvent::Resize(window.0, window.1));
+    app.run();
+    // put their terminal back before hanging up
+    drop(app);
+    match stream.shutdown(std::net::Shutdown::Both) {
+        // they hung up first
+        Err(e) if e.kind() == std::io::ErrorKind::NotConnected => Ok(()),
+        result => result,
+    }
+}
+
+/// Wait for the client to report its window size, or give up and assume
+/// one. None if they hang up first.
+fn negotiate(
+    stream: &mut std::net::TcpStream,
+    decoder: &mut crate::telnet::Decoder,
+) -> std::io::Result<Option<(u16, u16)>> {
+    let start = std::time::Instant::now();
+    let mut chunk = [0; 256];
+    while let Some(left) = NEGOTIATION_TIMEOUT.checked_sub(start.elapsed()) {
+        stream.set_read_timeout(Some(left.max(std::time::Duration::from_millis(1))))?;
+        let read = match stream.read(&mut chunk) {
+            Ok(0) => return Ok(None),
+            Ok(read) => read,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(e) => return Err(e),
+        };
+        // keys pressed this early can go
+        for event in decoder.decode(&chunk[..read]) {
+            if let crate::terminal::TerminalEvent::Resize(width, height) = event {
+                stream.set_read_timeout(None)?;
+                return Ok(Some((width, height)));
+            }
+        }
+    }
+    stream.set_read_timeout(None)?;
+    Ok(Some(DEFAULT_WINDOW))
+}
+
+/// What a player's client sends, read from the socket on another thread.
+#[derive(bevy::ecs::system::Resource)]
+struct Session {
+    decoder: crate::telnet::Decoder,
+    incoming: std::sync::Mutex<std::sync::mpsc::Receiver<Vec<u8>>>,
+}
+
+fn plugin(app: &mut bevy::app::App) {
+    app.add_systems(bevy::app::First, receive);
+}
+
+/// Pass on the player's keys and window size, and end the game once
+/// they've hung up or pressed `Ctrl+C`.
+fn receive(
+    mut session: bevy::ecs::system::ResMut<Session>,
+    mut terminal: bevy::ecs::system::ResMut<crate::terminal::Terminal>,
+    mut writer: bevy::ecs::event::EventWriter<crate::terminal::TerminalEvent>,
+    mut exit: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
+) {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    let session = &mut *session;
+    let incoming = session.incoming.get_mut().expect("session lock");
+    loop {
+        let bytes = match incoming.try_recv() {
+            Ok(bytes) => bytes,
+            Err(std::sync::mpsc::TryRecvError::Empty) => break,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                log::info!("Player hung up");
+                exit.send(bevy::app::AppExit);
+                return;
+            }
+        };
+        for event in session.decoder.decode(&bytes) {
+            match event {
+                crate::terminal::TerminalEvent::Key(key)
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && matches!(key.code, KeyCode::Char('c' | 'd')) =>
+                {
+                    exit.send(bevy::app::AppExit);
+                    return;
+                }
+                crate::terminal::TerminalEvent::Resize(width, height) => {
+                    terminal.resize(width, height);
+                    writer.send(event);
+                }
+                event => {
+                    writer.send(event);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::app::Screen;
+use crate::config::Config;
+use crate::input::Action;
+
+/// Frame rate caps to pick from.
+const FRAME_RATES: [u32; 4] = [30, 60, 120, 144];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Item {
+    Difficulty,
+    Adaptive,
+    Colour,
+    FrameRate,
+    Sound,
+    Bindings,
+    Back,
+}
+
+impl Item {
+    const ALL: [Item; 7] = [
+        Item::Difficulty,
+        Item::Adaptive,
+        Item::Colour,
+        Item::FrameRate,
+        Item::Sound,
+        Item::Bindings,
+        Item::Back,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Item::Difficulty => "Difficulty",
+            Item::Adaptive => "Adaptive",
+            Item::Colour => "Colour",
+            Item::FrameRate => "Frame rate",
+            Item::Sound => "Sound",
+            Item::Bindings => "Key bindings",
+            Item::Back => "Back",
+        }
+    }
+
+    fn value(&self, config: &Config) -> String {
+        match self {
+            Item::Difficulty => config.difficulty.name().to_string(),
+            Item::Colour => config.colour.name().to_string(),
+            Item::FrameRate => format!("{} fps", config.frame_rate),
+            Item::Adaptive => on_off(config.adaptive_difficulty),
+            Item::Sound => on_off(config.sound),
+            Item::Bindings | Item::Back => String::new(),
+        }
+    }
+
+    /// Step the setting one way or the other, returning whether it has a
+    /// value to change.
+    fn change(&self, config: &mut Config, forward: bool) -> bool {
+        match self {
+            Item::Difficulty => {
+                config.difficulty = step(
+                    &crate::difficulty::Difficulty::ALL,
+                    &config.difficulty,
+                    forward,
+                );
+            }
+            Item::Colour => {
+                use crate::terminal::ColourMode;
+                config.colour = step(
+                    &[ColourMode::Colour, ColourMode::Monochrome],
+                    &config.colour,
+                    forward,
+                );
+            }
+            Item::FrameRate => {
+                // a rate set by hand in the file steps to its neighbour
+                config.frame_rate = if forward {
+                    FRAME_RATES
+                        .into_iter()
+                        .find(|rate| *rate > config.frame_rate)
+                        .unwrap_or(FRAME_RATES[0])
+                } else {
+                    FRAME_RATES
+                        .into_iter()
+                        .rev()
+                        .find(|rate| *rate < config.frame_rate)
+                        .unwrap_or(FRAME_RATES[FRAME_RATES.len() - 1])
+                };
+            }
+            Item::Adaptive => config.adaptive_difficulty = !config.adaptive_difficulty,
+            Item::Sound => config.sound = !config.sound,
+            Item::Bindings | Item::Back => return false,
+        }
+        true
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "on" } else { "off" }.to_string()
+}
+
+/// The option before or after `current`, wrapping around.
+fn step<T: Copy + PartialEq>(options: &[T], current: &T, forward: bool) -> T {
+    let index = options.iter().position(|o| o == current).unwrap_or(0);
+    let len = options.len();
+    options[if forward {
+        (index + 1) % len
+    } else {
+        (index + len - 1) % len
+    }]
+}
+
+/// The settings menu, reached from the title. Changes apply straight away
+/// and are saved to the config file on the way out.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Settings {
+    selected: usize,
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::in_state, Condition, IntoSystemConfigs};
+
+    app.init_resource::<Settings>();
+    app.add_systems(
+        bevy::app::Update,
+        navigate.run_if(in_state(Screen::Settings)),
+    );
+    app.add_systems(bevy::ecs::schedule::OnExit(Screen::Settings), save);
+    app.add_systems(
+        bevy::app::PostUpdate,
+        render.run_if(in_state(Screen::Settings).and_then(bindings_closed)),
+    );
+}
+
+fn bindings_closed(screen: bevy::ecs::system::Res<crate::bindings_screen::BindingsScreen>) -> bool {
+    !screen.is_open()
+}
+
+fn navigate(
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut settings: bevy::ecs::system::ResMut<Settings>,
+    mut config: bevy::ecs::system::ResMut<Config>,
+    mut bindings_screen: bevy::ecs::system::ResMut<crate::bindings_screen::BindingsScreen>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+) {
+    for event in reader.read().filter(|event| event.pressed) {
+        let item = Item::ALL[settings.selected];
+        match event.action {
+            Action::MoveUp => {
+                settings.selected = (settings.selected + Item::ALL.len() - 1) % Item::ALL.len();
+            }
+            Action::MoveDown => settings.selected = (settings.selected + 1) % Item::ALL.len(),
+            Action::MoveLeft | Action::MoveRight
+                if item.change(&mut config, event.action == Action::MoveRight) =>
+            {
+                log::info!("{} set to {}", item.name(), item.value(&config));
+            }
+            Action::Fire => match item {
+                Item::Bindings => {
+                    bindings_screen.open(&mut time);
+                    return;
+                }
+                Item::Back => next_screen.set(Screen::Title),
+                _ => {
+                    item.change(&mut config, true);
+                    log::info!("{} set to {}", item.name(), item.value(&config));
+                }
+            },
+            Action::Quit => next_screen.set(Screen::Title),
+            _ => {}
+        }
+    }
+}
+
+fn save(
+    config: bevy::ecs::system::Res<Config>,
+    persist: bevy::ecs::system::Res<crate::config::Persist>,
+) {
+    if !persist.0 {
+        return;
+    }
+    if let Err(e) = config.save() {
+        log::error!("Failed to save config: {}", e);
+    }
+}
+
+fn render(
+    settings: bevy::ecs::system::Res<Settings>,
+    config: bevy::ecs::system::Res<Config>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    let rows: Vec<_> = Item::ALL
+        .iter()
+        .map(|item| (item.name(), item.value(&config)))
+        .collect();
+    let selected = settings.selected;
+
+    overlays.push(move |frame, area| {
+        let area = crate::terminal::centered(area, 36, rows.len() as u16 + 2);
+        let table = ratatui::widgets::Table::new(
+            rows.into_iter().enumerate().map(|(i, (name, value))| {
+                let value = if i == selected && !value.is_empty() {
+                    format!("< {} >", value)
+                } else {
+                    value
+                };
+                let row = ratatui::widgets::Row::new([name.to_string(), value]);
+                if i == selected {
+                    row.style(
+                        ratatui::style::Style::new()
+                            .add_modifier(ratatui::style::Modifier::REVERSED),
+                    )
+                } else {
+                    row
+                }
+            }),
+            [
+                ratatui::layout::Constraint::Length(14),
+                ratatui::layout::Constraint::Fill(1),
+            ],
+        )
+        .block(
+            ratatui::widgets::Block::default()
+                .title(" Settings ")
+                .title(
+                    ratatui::widgets::block::Title::from(" ←→ change  Esc back ")
+                        .position(ratatui::widgets::block::Position::Bottom),
+                )
+                .borders(ratatui::widgets::Borders::ALL),
+        );
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(table, area);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn settings_cycle() {
+        use crate::difficulty::Difficulty;
+
+        let mut config = Config::default();
+        assert!(Item::Difficulty.change(&mut config, false));
+        assert_eq!(config.difficulty, Difficulty::Easy);
+        Item::Difficulty.change(&mut config, false);
+        assert_eq!(config.difficulty, Difficulty::Insane);
+
+        Item::Sound.change(&mut config, true);
+        assert!(!config.sound);
+        assert!(!Item::Bindings.change(&mut config, true));
+    }
+
+    #[test]
+    fn frame_rate_steps_to_neighbours() {
+        let mut config = Config {
+            frame_rate: 90,
+            ..Default::default()
+        };
+        Item::FrameRate.change(&mut config, true);
+        assert_eq!(config.frame_rate, 120);
+        Item::FrameRate.change(&mut config, true);
+        Item::FrameRate.change(&mut config, true);
+        assert_eq!(config.frame_rate, 30);
+        Item::FrameRate.change(&mut config, false);
+        assert_eq!(config.frame_rate, 144);
+    }
+}
@@ -0,0 +1,360 @@
+//! Streams the game to anyone watching with `shellaga watch`, as the same
+//! escape codes which draw it in a terminal.
+
+use std::io::{Read, Write};
+
+/// Give up on a spectator with this much still waiting to be sent to them.
+const MAX_BACKLOG: usize = 1024 * 1024;
+
+/// A connection to or from a spectator.
+pub trait Stream: Read + Write + Send + Sync {}
+
+impl<T: Read + Write + Send + Sync> Stream for T {}
+
+/// Addresses with a `/` in them are Unix socket paths, the rest TCP.
+fn is_socket_path(address: &str) -> bool {
+    address.contains('/')
+}
+
+enum Listener {
+    Tcp(std::net::TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener, std::path::PathBuf),
+}
+
+impl Listener {
+    fn bind(address: &str) -> std::io::Result<Self> {
+        let listener = if is_socket_path(address) {
+            #[cfg(unix)]
+            {
+                let listener = std::os::unix::net::UnixListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                Listener::Unix(listener, address.into())
+            }
+            #[cfg(not(unix))]
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Unix sockets aren't supported here",
+            ));
+        } else {
+            let listener = std::net::TcpListener::bind(address)?;
+            listener.set_nonblocking(true)?;
+            Listener::Tcp(listener)
+        };
+        Ok(listener)
+    }
+
+    fn local_address(&self) -> String {
+        match self {
+            Listener::Tcp(listener) => listener
+                .local_addr()
+                .map_or_else(|e| e.to_string(), |address| address.to_string()),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => path.display().to_string(),
+        }
+    }
+
+    /// The next spectator waiting to connect, if any.
+    fn accept(&self) -> std::io::Result<Option<Box<dyn Stream>>> {
+        let stream: Box<dyn Stream> = match self {
+            Listener::Tcp(listener) => match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    Box::new(stream)
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e),
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    Box::new(stream)
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e),
+            },
+        };
+        Ok(Some(stream))
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Connect to a game being streamed at `address`, without blocking on
+/// reads.
+pub fn connect(address: &str) -> std::io::Result<Box<dyn Stream>> {
+    if is_socket_path(address) {
+        #[cfg(unix)]
+        {
+            let stream = std::os::unix::net::UnixStream::connect(address)?;
+            stream.set_nonblocking(true)?;
+            return Ok(Box::new(stream));
+        }
+        #[cfg(not(unix))]
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix sockets aren't supported here",
+        ));
+    }
+    let stream = std::net::TcpStream::connect(address)?;
+    stream.set_nonblocking(true)?;
+    Ok(Box::new(stream))
+}
+
+/// Collects what the spectators' terminal writes, to be sent on to each.
+#[derive(Clone, Default)]
+struct Recording(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl Recording {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.lock().expect("recording lock"))
+    }
+}
+
+impl Write for Recording {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .expect("recording lock")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Spectator {
+    stream: Box<dyn Stream>,
+    outgoing: Vec<u8>,
+}
+
+impl Spectator {
+    /// Send as much as the spectator will take.
+    fn flush(&mut self) -> std::io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if self.outgoing.len() > MAX_BACKLOG {
+            return Err(std::io::Error::other("fallen too far behind"));
+        }
+        Ok(())
+    }
+
+    /// Whether the spectator has asked for the whole frame again, which
+    /// they do by sending anything at all. Errors once they've gone.
+    fn wants_redraw(&mut self) -> std::io::Result<bool> {
+        let mut chunk = [0; 64];
+        let mut redraw = false;
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => redraw = true,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(redraw),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Everyone watching, and a terminal of their own which draws each frame
+/// as changes to the one before.
+#[derive(bevy::ecs::system::Resource)]
+pub struct Spectators {
+    listener: Listener,
+    terminal: ratatui::Terminal<crate::terminal::Backend>,
+    recording: Recording,
+    spectators: Vec<Spectator>,
+}
+
+impl Spectators {
+    pub fn bind(address: &str) -> std::io::Result<Self> {
+        let recording = Recording::default();
+        let terminal = spectator_terminal(recording.clone(), &crate::frame::Size::default())?;
+        let spectators = Self {
+            listener: Listener::bind(address)?,
+            terminal,
+            recording,
+            spectators: Vec::new(),
+        };
+        log::info!("Streaming to spectators on {}", spectators.local_address());
+        Ok(spectators)
+    }
+
+    pub fn local_address(&self) -> String {
+        self.listener.local_address()
+    }
+}
+
+fn spectator_terminal(
+    recording: Recording,
+    size: &crate::frame::Size,
+) -> std::io::Result<ratatui::Terminal<crate::terminal::Backend>> {
+    let (width, height) = crate::terminal::required_size(size);
+    ratatui::Terminal::with_options(
+        crate::terminal::Backend::new(
+            Box::new(recording),
+            std::sync::Arc::default(),
+            Some((width, height)),
+        ),
+        ratatui::TerminalOptions {
+            viewport: ratatui::Viewport::Fixed(ratatui::layout::Rect::new(0, 0, width, height)),
+        },
+    )
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    // headless servers have no terminal to bring one
+    app.init_resource::<crate::terminal::Hud>();
+    app.add_systems(bevy::app::Last, stream.after(crate::subcell::merge));
+}
+
+fn stream(
+    mut spectators: bevy::ecs::system::ResMut<Spectators>,
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    buffer: bevy::ecs::system::Res<crate::buffer::Buffer>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    hud: bevy::ecs::system::Res<crate::terminal::Hud>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+) {
+    let spectators = &mut *spectators;
+    let mut redraw = false;
+    loop {
+        match spectators.listener.accept() {
+            Ok(Some(stream)) => {
+                log::info!("A spectator joined");
+                spectators.spectators.push(Spectator {
+                    stream,
+                    outgoing: Vec::new(),
+                });
+                redraw = true;
+            }
+            Ok(None) => break,
+            Err(e) => {
+                log::error!("Failed to accept a spectator: {}", e);
+                break;
+            }
+        }
+    }
+    spectators
+        .spectators
+        .retain_mut(|spectator| match spectator.wants_redraw() {
+            Ok(wants) => {
+                redraw |= wants;
+                true
+            }
+            Err(e) => {
+                log::info!("A spectator left: {}", e);
+                false
+            }
+        });
+    if spectators.spectators.is_empty() {
+        return;
+    }
+
+    let (width, height) = crate::terminal::required_size(&size);
+    if spectators.terminal.get_frame().size() != ratatui::layout::Rect::new(0, 0, width, height) {
+        match spectator_terminal(spectators.recording.clone(), &size) {
+            Ok(terminal) => spectators.terminal = terminal,
+            Err(e) => log::error!("Failed to resize the spectators' terminal: {}", e),
+        }
+        redraw = true;
+    }
+    if redraw {
+        // everyone gets the whole frame, which is cheaper than a terminal each
+        if let Err(e) = spectators.terminal.clear() {
+            log::error!("Failed to clear the spectators' terminal: {}", e);
+        }
+    }
+    if let Err(e) = crate::terminal::fallible_render(
+        &mut spectators.terminal,
+        &size,
+        &buffer,
+        &score,
+        &hud,
+        config.colour,
+        Vec::new(),
+    ) {
+        log::error!("Failed to render for spectators: {}", e);
+    }
+
+    let frame = spectators.recording.take();
+    spectators.spectators.retain_mut(|spectator| {
+        spectator.outgoing.extend_from_slice(&frame);
+        match spectator.flush() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Dropped a spectator: {}", e);
+                false
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn socket_paths_are_told_apart() {
+        assert!(is_socket_path("/tmp/shellaga.sock"));
+        assert!(is_socket_path("./shellaga.sock"));
+        assert!(!is_socket_path("127.0.0.1:7778"));
+        assert!(!is_socket_path("localhost:7778"));
+    }
+
+    #[test]
+    fn later_frames_send_only_changes() {
+        let size = crate::frame::Size::default();
+        let recording = Recording::default();
+        let mut terminal = spectator_terminal(recording.clone(), &size).unwrap();
+        let mut buffer = crate::buffer::Buffer(ndarray::Array2::from_elem(
+            (size.height, size.width),
+            Default::default(),
+        ));
+        let score = crate::score::Score::default();
+        let mut render = |buffer: &crate::buffer::Buffer| {
+            crate::terminal::fallible_render(
+                &mut terminal,
+                &size,
+                buffer,
+                &score,
+                &crate::terminal::Hud::default(),
+                crate::terminal::ColourMode::Colour,
+                Vec::new(),
+            )
+            .unwrap();
+            String::from_utf8(recording.take()).unwrap()
+        };
+
+        let first = render(&buffer);
+        assert!(first.contains("Shellaga"));
+        buffer.0[[3, 5]].character = Some('x'.into());
+        let second = render(&buffer);
+        assert!(!second.contains("Shellaga"));
+        // the border takes a row and a column, and the terminal counts from one
+        assert!(second.contains("\x1b[5;7Hx"));
+        assert_eq!(second.matches('x').count(), 1);
+    }
+}
@@ -1,5 +1,10 @@
 pub fn plugin(app: &mut bevy::app::App) {
-    app.add_systems(bevy::app::PostUpdate, render);
+    use bevy::ecs::schedule::{common_conditions::any_with_component, IntoSystemConfigs};
+
+    app.add_systems(
+        bevy::app::PostUpdate,
+        render.run_if(any_with_component::<crate::frame::Frame>),
+    );
 }
 
 #[derive(bevy::ecs::component::Component, Clone, Default)]
@@ -8,30 +13,134 @@ pub struct Sprite {
     // origin: bevy::math::i32::IVec2,
 }
 
+/// Keeps a sprite from being drawn, without taking it out of the game.
+#[derive(bevy::ecs::component::Component)]
+pub struct Hidden;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SpriteError {
+    /// A control character or something else which takes up no columns.
+    Unprintable(String),
+    /// Wider than the two columns a glyph can fill.
+    TooWide(String),
+    /// Too long to fit in a cell.
+    TooLong(String),
+}
+
+impl std::fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpriteError::Unprintable(cluster) => write!(f, "{:?} can't be printed", cluster),
+            SpriteError::TooWide(cluster) => write!(f, "{:?} is too wide", cluster),
+            SpriteError::TooLong(cluster) => write!(f, "{:?} is too long", cluster),
+        }
+    }
+}
+
+impl std::error::Error for SpriteError {}
+
+impl Sprite {
+    /// Build a sprite from rows of text, where spaces are transparent.
+    ///
+    /// Double width glyphs fill the cell to their right with a
+    /// continuation, so every row lines up by display width.
+    pub fn from_lines(lines: &[&str]) -> Result<Self, SpriteError> {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let mut rows = Vec::new();
+        for line in lines {
+            let mut row = Vec::new();
+            for cluster in line.graphemes(true) {
+                if cluster == " " {
+                    row.push(None);
+                    continue;
+                }
+                let glyph = crate::buffer::Glyph::new(cluster)
+                    .ok_or_else(|| SpriteError::TooLong(cluster.to_string()))?;
+                if cluster.chars().any(char::is_control) {
+                    return Err(SpriteError::Unprintable(cluster.to_string()));
+                }
+                match glyph.width() {
+                    0 => return Err(SpriteError::Unprintable(cluster.to_string())),
+                    1 => row.push(Some(glyph)),
+                    2 => row.extend([Some(glyph), Some(crate::buffer::Glyph::CONTINUATION)]),
+                    _ => return Err(SpriteError::TooWide(cluster.to_string())),
+                }
+            }
+            rows.push(row);
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let buffer =
+            ndarray::Array2::from_shape_fn((rows.len(), width), |(row, col)| crate::buffer::Cell {
+                character: rows[row].get(col).copied().flatten(),
+                ..Default::default()
+            });
+        Ok(Self {
+            buffer: crate::buffer::Buffer(buffer),
+        })
+    }
+}
+
+/// Sprites which are shown, other than the frame.
+type Drawn = (
+    bevy::ecs::query::Without<crate::frame::Frame>,
+    bevy::ecs::query::Without<Hidden>,
+);
+
 fn render(
     mut buffer: bevy::ecs::system::ResMut<crate::buffer::Buffer>,
+    resolution: bevy::ecs::system::Res<crate::subcell::Resolution>,
     query: bevy::ecs::system::Query<
-        (&Sprite, &bevy::transform::components::GlobalTransform),
-        bevy::ecs::query::Without<crate::frame::Frame>,
+        (
+            &Sprite,
+            &bevy::transform::components::GlobalTransform,
+            bevy::ecs::query::Has<crate::subcell::Particle>,
+        ),
+        Drawn,
     >,
     frame_query: bevy::ecs::system::Query<
-        &bevy::transform::components::GlobalTransform,
+        (
+            &bevy::transform::components::GlobalTransform,
+            &crate::collider::Collider,
+        ),
         bevy::ecs::query::With<crate::frame::Frame>,
     >,
 ) {
-    let Ok(frame_transform) = frame_query.get_single() else {
+    let Ok((frame_global_transform, frame_collider)) = frame_query.get_single() else {
         log::error!("Could not get unique frame");
         return;
     };
-    let frame_transform = frame_transform.compute_matrix().inverse();
-    for (sprite, global_transform) in &query {
+    let frame_transform = frame_global_transform.compute_matrix().inverse();
+    for (sprite, global_transform, particle) in &query {
+        if particle && resolution.is_fine() {
+            continue;
+        }
+        // cull before any per-cell work, most of a level is out of view
+        let position = global_transform.translation() - frame_global_transform.translation();
+        if !in_frame(position, sprite.buffer.0.dim(), **frame_collider) {
+            continue;
+        }
         let transform = bevy::transform::components::Transform::from_matrix(
             global_transform.compute_matrix() * frame_transform,
         );
-        render_to_buffer(sprite, &transform, &mut *buffer);
+        render_to_buffer(sprite, &transform, &mut buffer);
     }
 }
 
+/// Whether a sprite of `rows` by `columns` at `position` in frame space
+/// overlaps a frame of `size`.
+fn in_frame(
+    position: bevy::math::f32::Vec3,
+    (rows, columns): (usize, usize),
+    size: bevy::math::f32::Vec2,
+) -> bool {
+    position.x + columns as f32 > 0.0
+        && position.x < size.x
+        && position.y + rows as f32 > 0.0
+        && position.y < size.y
+}
+
 fn render_to_buffer(
     sprite: &Sprite,
     transform: &bevy::transform::components::Transform,
@@ -68,9 +177,58 @@ fn render_to_buffer(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buffer::Cell;
+    use crate::buffer::{Cell, Glyph};
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn sprite_from_lines() {
+        let sprite = Sprite::from_lines(&["]o>", " 🚀x", "e\u{301}"]).unwrap();
+        let characters = sprite.buffer.0.map(|cell| cell.character);
+        assert_eq!(
+            characters,
+            ndarray::array![
+                [Some(']'.into()), Some('o'.into()), Some('>'.into()), None],
+                [
+                    None,
+                    Some('🚀'.into()),
+                    Some(Glyph::CONTINUATION),
+                    Some('x'.into())
+                ],
+                [Glyph::new("e\u{301}"), None, None, None],
+            ]
+        );
+    }
+
+    #[test]
+    fn sprite_widths_are_validated() {
+        assert_eq!(
+            Sprite::from_lines(&["a\tb"]).err(),
+            Some(SpriteError::Unprintable("\t".to_string()))
+        );
+        assert_eq!(
+            Sprite::from_lines(&["\u{200b}"]).err(),
+            Some(SpriteError::Unprintable("\u{200b}".to_string()))
+        );
+        // a conjunct which renders three columns wide
+        assert_eq!(
+            Sprite::from_lines(&["\u{915}\u{94d}\u{937}\u{93f}"]).err(),
+            Some(SpriteError::TooWide(
+                "\u{915}\u{94d}\u{937}\u{93f}".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn sprites_outside_frame_are_culled() {
+        use bevy::math::f32::{Vec2, Vec3};
+        let frame = Vec2::new(128.0, 32.0);
+        assert!(in_frame(Vec3::new(0.0, 0.0, 0.0), (5, 2), frame));
+        assert!(in_frame(Vec3::new(-1.5, 30.0, 0.0), (5, 2), frame));
+        assert!(!in_frame(Vec3::new(-2.0, 10.0, 0.0), (5, 2), frame));
+        assert!(!in_frame(Vec3::new(128.0, 10.0, 0.0), (5, 2), frame));
+        assert!(!in_frame(Vec3::new(10.0, -5.0, 0.0), (5, 2), frame));
+    }
+
     #[test]
     fn render_to_buffer_test() {
         let mut buffer = crate::buffer::Buffer(ndarray::Array2::from_elem(
@@ -79,7 +237,7 @@ mod tests {
         ));
         let sprite = Sprite {
             buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 ..Default::default()
             }]]),
         };
@@ -90,7 +248,7 @@ mod tests {
             buffer,
             crate::buffer::Buffer(
                 ndarray::array![[Some('x'), None, None], [None, None, None],].mapv(|c| Cell {
-                    character: c,
+                    character: c.map(Glyph::from),
                     ..Default::default()
                 })
             )
@@ -105,7 +263,7 @@ mod tests {
         ));
         let sprite = Sprite {
             buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 ..Default::default()
             }]]),
         };
@@ -122,7 +280,7 @@ mod tests {
             buffer,
             crate::buffer::Buffer(
                 ndarray::array![[None, Some('x'), None], [None, None, None],].mapv(|c| Cell {
-                    character: c,
+                    character: c.map(Glyph::from),
                     ..Default::default()
                 })
             )
@@ -137,7 +295,7 @@ mod tests {
         ));
         let sprite = Sprite {
             buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 ..Default::default()
             }]]),
         };
@@ -154,7 +312,7 @@ mod tests {
             buffer,
             crate::buffer::Buffer(
                 ndarray::array![[None, None, None], [Some('x'), None, None],].mapv(|c| Cell {
-                    character: c,
+                    character: c.map(Glyph::from),
                     ..Default::default()
                 })
             )
@@ -172,18 +330,18 @@ mod tests {
             buffer: crate::buffer::Buffer(ndarray::array![
                 [
                     crate::buffer::Cell {
-                        character: Some('x'),
+                        character: Some('x'.into()),
                         ..Default::default()
                     },
                     crate::buffer::Cell {
-                        character: Some('x'),
+                        character: Some('x'.into()),
                         ..Default::default()
                     }
                 ],
                 [
                     Default::default(),
                     crate::buffer::Cell {
-                        character: Some('x'),
+                        character: Some('x'.into()),
                         ..Default::default()
                     }
                 ],
@@ -197,7 +355,7 @@ mod tests {
             crate::buffer::Buffer(
                 ndarray::array![[Some('x'), Some('x'), None], [None, Some('x'), None],].mapv(|c| {
                     Cell {
-                        character: c,
+                        character: c.map(Glyph::from),
                         ..Default::default()
                     }
                 })
@@ -208,14 +366,14 @@ mod tests {
     #[test]
     fn render_to_buffer_with_existing_texel_in_front() {
         let mut buffer = crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-            character: Some('a'),
+            character: Some('a'.into()),
             depth: -1.0,
             ..Default::default()
         }]]);
 
         let sprite = Sprite {
             buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 ..Default::default()
             },],]),
         };
@@ -230,14 +388,14 @@ mod tests {
     #[test]
     fn render_to_buffer_with_existing_texel_behind() {
         let mut buffer = crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-            character: Some('a'),
+            character: Some('a'.into()),
             depth: -1.0,
             ..Default::default()
         }]]);
 
         let sprite = Sprite {
             buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 ..Default::default()
             },],]),
         };
@@ -253,7 +411,7 @@ mod tests {
         assert_eq!(
             buffer,
             crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                character: Some('x'),
+                character: Some('x'.into()),
                 depth: -2.0,
                 ..Default::default()
             }]])
@@ -0,0 +1,253 @@
+use crate::buffer::Color;
+
+/// How finely the background, particles and shots are drawn.
+#[derive(
+    bevy::ecs::system::Resource, clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub enum Resolution {
+    /// One character per cell
+    #[default]
+    Cell,
+    /// Upper and lower half blocks, doubling the vertical resolution
+    HalfBlock,
+    /// Braille patterns, two dots across and four down each cell
+    Braille,
+}
+
+impl Resolution {
+    /// Dots across and down each cell.
+    pub fn scale(self) -> (usize, usize) {
+        match self {
+            Resolution::Cell => (1, 1),
+            Resolution::HalfBlock => (1, 2),
+            Resolution::Braille => (2, 4),
+        }
+    }
+
+    pub fn is_fine(self) -> bool {
+        self != Resolution::Cell
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dot {
+    pub fg: Color,
+    pub depth: f32,
+}
+
+/// A buffer of dots at a multiple of the frame's resolution, merged into
+/// the cell buffer just before it's drawn.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Dots(pub ndarray::Array2<Option<Dot>>);
+
+impl Dots {
+    /// Plot a dot unless there's one in front of it already.
+    pub fn plot(&mut self, x: usize, y: usize, dot: Dot) {
+        if let Some(existing) = self.0.get_mut([y, x]) {
+            if existing.is_none_or(|existing| dot.depth < existing.depth) {
+                *existing = Some(dot);
+            }
+        }
+    }
+}
+
+/// Drawn as a line one dot high across its cell in the sub-cell
+/// resolutions, in place of its sprite.
+#[derive(bevy::ecs::component::Component, Clone, Copy, Debug)]
+pub struct Particle {
+    pub fg: Color,
+    pub depth: f32,
+}
+
+/// Braille dot bits indexed by row then column within the cell.
+const BRAILLE: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.init_resource::<Resolution>();
+    app.init_resource::<Dots>();
+    app.add_systems(bevy::app::First, clear_dots.run_if(is_fine));
+    app.add_systems(
+        bevy::app::PostUpdate,
+        render_particles.run_if(is_fine).run_if(
+            bevy::ecs::schedule::common_conditions::any_with_component::<crate::frame::Frame>,
+        ),
+    );
+    app.add_systems(
+        bevy::app::Last,
+        merge.run_if(is_fine).before(crate::terminal::render),
+    );
+}
+
+pub fn is_fine(resolution: bevy::ecs::system::Res<Resolution>) -> bool {
+    resolution.is_fine()
+}
+
+fn clear_dots(
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    resolution: bevy::ecs::system::Res<Resolution>,
+    mut dots: bevy::ecs::system::ResMut<Dots>,
+) {
+    let (scale_x, scale_y) = resolution.scale();
+    let dim = (size.height * scale_y, size.width * scale_x);
+    if dots.0.dim() == dim {
+        dots.0.fill(None);
+    } else {
+        dots.0 = ndarray::Array2::from_elem(dim, None);
+    }
+}
+
+fn render_particles(
+    resolution: bevy::ecs::system::Res<Resolution>,
+    mut dots: bevy::ecs::system::ResMut<Dots>,
+    query: bevy::ecs::system::Query<(&Particle, &bevy::transform::components::GlobalTransform)>,
+    frame_query: bevy::ecs::system::Query<
+        &bevy::transform::components::GlobalTransform,
+        bevy::ecs::query::With<crate::frame::Frame>,
+    >,
+) {
+    let Ok(frame_transform) = frame_query.get_single() else {
+        log::error!("Could not get unique frame");
+        return;
+    };
+    let frame_inverse = frame_transform.compute_matrix().inverse();
+    let (scale_x, scale_y) = resolution.scale();
+    for (particle, global_transform) in &query {
+        let translation = bevy::transform::components::Transform::from_matrix(
+            global_transform.compute_matrix() * frame_inverse,
+        )
+        .translation;
+        if translation.x < 0.0 || translation.y < 0.0 {
+            continue;
+        }
+        let x = (translation.x * scale_x as f32).round() as usize;
+        let y = ((translation.y + 0.5) * scale_y as f32) as usize;
+        for x in x..x + scale_x {
+            dots.plot(
+                x,
+                y,
+                Dot {
+                    fg: particle.fg,
+                    depth: translation.z + particle.depth,
+                },
+            );
+        }
+    }
+}
+
+pub fn merge(
+    resolution: bevy::ecs::system::Res<Resolution>,
+    dots: bevy::ecs::system::Res<Dots>,
+    mut buffer: bevy::ecs::system::ResMut<crate::buffer::Buffer>,
+) {
+    merge_into(*resolution, &dots, &mut buffer);
+}
+
+/// Draw each cell's dots in front of it as a single glyph.
+fn merge_into(resolution: Resolution, dots: &Dots, buffer: &mut crate::buffer::Buffer) {
+    let (scale_x, scale_y) = resolution.scale();
+    for ((row, col), cell) in buffer.0.indexed_iter_mut() {
+        let mut bits = 0;
+        let mut nearest: Option<Dot> = None;
+        for (dy, dx) in itertools::iproduct!(0..scale_y, 0..scale_x) {
+            let Some(Some(dot)) = dots.0.get([row * scale_y + dy, col * scale_x + dx]) else {
+                continue;
+            };
+            if dot.depth > cell.depth {
+                continue;
+            }
+            bits |= match resolution {
+                Resolution::Cell => 1,
+                Resolution::HalfBlock => 1 << dy,
+                Resolution::Braille => BRAILLE[dy][dx],
+            };
+            if nearest.is_none_or(|nearest| dot.depth < nearest.depth) {
+                nearest = Some(*dot);
+            }
+        }
+        let Some(nearest) = nearest else {
+            continue;
+        };
+        *cell = crate::buffer::Cell {
+            character: glyph(resolution, bits).map(crate::buffer::Glyph::from),
+            fg: nearest.fg,
+            depth: nearest.depth,
+            ..Default::default()
+        };
+    }
+}
+
+fn glyph(resolution: Resolution, bits: u32) -> Option<char> {
+    match resolution {
+        Resolution::Cell => Some('.'),
+        Resolution::HalfBlock => match bits {
+            0b01 => Some('▀'),
+            0b10 => Some('▄'),
+            _ => Some('█'),
+        },
+        Resolution::Braille => char::from_u32(0x2800 + bits),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn dots(lit: &[(usize, usize)], width: usize, height: usize) -> Dots {
+        let mut dots = Dots(ndarray::Array2::from_elem((height, width), None));
+        for &(x, y) in lit {
+            dots.plot(
+                x,
+                y,
+                Dot {
+                    fg: Color::White,
+                    depth: 0.0,
+                },
+            );
+        }
+        dots
+    }
+
+    fn merged(resolution: Resolution, dots: &Dots, width: usize) -> String {
+        let mut buffer = crate::buffer::Buffer(ndarray::Array2::from_elem(
+            (1, width),
+            crate::buffer::Cell {
+                depth: f32::MAX,
+                ..Default::default()
+            },
+        ));
+        merge_into(resolution, dots, &mut buffer);
+        buffer
+            .0
+            .iter()
+            .map(|cell| cell.character.map_or(" ".to_string(), |c| c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn half_blocks() {
+        let dots = dots(&[(0, 0), (1, 1), (2, 0), (2, 1)], 4, 2);
+        assert_eq!(merged(Resolution::HalfBlock, &dots, 4), "▀▄█ ");
+    }
+
+    #[test]
+    fn braille_patterns() {
+        // a diagonal through the first cell and the bottom row of the second
+        let dots = dots(&[(0, 0), (1, 1), (2, 3), (3, 3)], 4, 4);
+        assert_eq!(merged(Resolution::Braille, &dots, 2), "⠑⣀");
+    }
+
+    #[test]
+    fn cells_in_front_hide_dots() {
+        let mut buffer = crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
+            character: Some('>'.into()),
+            depth: -1.0,
+            ..Default::default()
+        }]]);
+        let unchanged = buffer.clone();
+        merge_into(Resolution::HalfBlock, &dots(&[(0, 0)], 1, 2), &mut buffer);
+        assert_eq!(buffer, unchanged);
+    }
+}
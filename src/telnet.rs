@@ -0,0 +1,248 @@
+//! Just enough of telnet to play over it: the options which put a client in
+//! character mode and report its window size, and the keys it sends.
+
+use crate::terminal::TerminalEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Interpret as command.
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+/// Subnegotiation begin and end.
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+/// Negotiate about window size.
+const NAWS: u8 = 31;
+
+const ESCAPE: u8 = 0x1b;
+
+/// Sent on connecting: the server echoes, so the client doesn't wait for
+/// whole lines, and the client reports its size now and whenever it
+/// changes.
+pub const NEGOTIATION: [u8; 9] = [IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DO, NAWS];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Data,
+    Command,
+    /// The option of a WILL, WONT, DO or DONT, which we don't answer.
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+/// Turns what a telnet client sends into key presses and resizes.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    state: State,
+    subnegotiation: Vec<u8>,
+    /// Data which may be the start of an escape sequence or character still
+    /// arriving.
+    data: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<TerminalEvent> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) => State::Command,
+                (State::Data, _) => {
+                    self.data.push(byte);
+                    State::Data
+                }
+                // an escaped 255 is data, which no key sends
+                (State::Command, IAC) => State::Data,
+                (State::Command, WILL | WONT | DO | DONT) => State::Option,
+                (State::Command, SB) => {
+                    self.subnegotiation.clear();
+                    State::Subnegotiation
+                }
+                (State::Command, _) | (State::Option, _) => State::Data,
+                (State::Subnegotiation, IAC) => State::SubnegotiationCommand,
+                (State::Subnegotiation, _) => {
+                    self.subnegotiation.push(byte);
+                    State::Subnegotiation
+                }
+                (State::SubnegotiationCommand, SE) => {
+                    if let [NAWS, width_high, width_low, height_high, height_low] =
+                        self.subnegotiation[..]
+                    {
+                        events.push(TerminalEvent::Resize(
+                            u16::from_be_bytes([width_high, width_low]),
+                            u16::from_be_bytes([height_high, height_low]),
+                        ));
+                    }
+                    State::Data
+                }
+                (State::SubnegotiationCommand, _) => {
+                    // a doubled IAC, which a size of 255 is sent as
+                    self.subnegotiation.push(byte);
+                    State::Subnegotiation
+                }
+            };
+        }
+        events.extend(self.keys().into_iter().map(TerminalEvent::Key));
+        events
+    }
+
+    /// The keys in the data so far, leaving anything incomplete for next
+    /// time except a lone escape, which is the escape key.
+    fn keys(&mut self) -> Vec<KeyEvent> {
+        let mut keys = Vec::new();
+        let mut start = 0;
+        while start < self.data.len() {
+            match parse_key(&self.data[start..]) {
+                Some((key, length)) => {
+                    keys.extend(key);
+                    start += length;
+                }
+                None => break,
+            }
+        }
+        self.data.drain(..start);
+        keys
+    }
+}
+
+/// The key at the start of `data` and how many bytes it took, None if it
+/// hasn't all arrived yet.
+fn parse_key(data: &[u8]) -> Option<(Option<KeyEvent>, usize)> {
+    let key = |code| Some(KeyEvent::new(code, KeyModifiers::NONE));
+    let (code, length) = match data {
+        [] => return None,
+        [ESCAPE] => (KeyCode::Esc, 1),
+        [ESCAPE, b'[' | b'O', rest @ ..] => {
+            return parse_sequence(rest).map(|(key, length)| (key, length + 2))
+        }
+        [ESCAPE, ..] => (KeyCode::Esc, 1),
+        // telnet sends return as CR LF or CR NUL
+        [b'\r', b'\n' | 0, ..] => (KeyCode::Enter, 2),
+        [b'\r' | b'\n', ..] => (KeyCode::Enter, 1),
+        [b'\t', ..] => (KeyCode::Tab, 1),
+        [0x7f | 0x08, ..] => (KeyCode::Backspace, 1),
+        [0, ..] => return Some((None, 1)),
+        [control @ 0x01..=0x1a, ..] => {
+            return Some((
+                Some(KeyEvent::new(
+                    KeyCode::Char((b'a' + control - 1) as char),
+                    KeyModifiers::CONTROL,
+                )),
+                1,
+            ))
+        }
+        [first, ..] => {
+            let length = match first {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            if data.len() < length {
+                return None;
+            }
+            return Some(match std::str::from_utf8(&data[..length]) {
+                Ok(text) => (
+                    text.chars().next().and_then(|c| key(KeyCode::Char(c))),
+                    length,
+                ),
+                // not text, so skip it
+                Err(_) => (None, 1),
+            });
+        }
+    };
+    Some((key(code), length))
+}
+
+/// The key for the rest of a CSI or SS3 sequence, and how many bytes it
+/// took.
+fn parse_sequence(data: &[u8]) -> Option<(Option<KeyEvent>, usize)> {
+    let end = data.iter().position(|byte| (0x40..=0x7e).contains(byte))?;
+    let parameters = std::str::from_utf8(&data[..end]).unwrap_or_default();
+    let number = parameters
+        .split(';')
+        .next()
+        .and_then(|number| number.parse::<u8>().ok());
+    let code = match (data[end], number) {
+        (b'A', _) => Some(KeyCode::Up),
+        (b'B', _) => Some(KeyCode::Down),
+        (b'C', _) => Some(KeyCode::Right),
+        (b'D', _) => Some(KeyCode::Left),
+        (b'H', _) | (b'~', Some(1 | 7)) => Some(KeyCode::Home),
+        (b'F', _) | (b'~', Some(4 | 8)) => Some(KeyCode::End),
+        (b'P', _) => Some(KeyCode::F(1)),
+        (b'Q', _) => Some(KeyCode::F(2)),
+        (b'R', _) => Some(KeyCode::F(3)),
+        (b'S', _) => Some(KeyCode::F(4)),
+        (b'~', Some(2)) => Some(KeyCode::Insert),
+        (b'~', Some(3)) => Some(KeyCode::Delete),
+        (b'~', Some(5)) => Some(KeyCode::PageUp),
+        (b'~', Some(6)) => Some(KeyCode::PageDown),
+        (b'~', Some(number @ 11..=15)) => Some(KeyCode::F(number - 10)),
+        (b'~', Some(number @ 17..=21)) => Some(KeyCode::F(number - 11)),
+        (b'~', Some(number @ 23..=24)) => Some(KeyCode::F(number - 12)),
+        _ => None,
+    };
+    Some((
+        code.map(|code| KeyEvent::new(code, KeyModifiers::NONE)),
+        end + 1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn codes(events: Vec<TerminalEvent>) -> Vec<KeyCode> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::Key(key) => Some(key.code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_are_decoded() {
+        let mut decoder = Decoder::default();
+        assert_eq!(
+            codes(decoder.decode(b"w \x1b[A\x1bOR\r\n\x1b[15~\x7f\xc3\xa9")),
+            [
+                KeyCode::Char('w'),
+                KeyCode::Char(' '),
+                KeyCode::Up,
+                KeyCode::F(3),
+                KeyCode::Enter,
+                KeyCode::F(5),
+                KeyCode::Backspace,
+                KeyCode::Char('é'),
+            ]
+        );
+        assert_eq!(codes(decoder.decode(b"\x1b")), [KeyCode::Esc]);
+    }
+
+    #[test]
+    fn split_sequences_wait_for_the_rest() {
+        let mut decoder = Decoder::default();
+        assert_eq!(codes(decoder.decode(b"\x1b[")), []);
+        assert_eq!(codes(decoder.decode(b"D\xe2\x99")), [KeyCode::Left]);
+        assert_eq!(codes(decoder.decode(b"\xa5")), [KeyCode::Char('♥')]);
+    }
+
+    #[test]
+    fn window_size_is_negotiated() {
+        let mut decoder = Decoder::default();
+        let events = decoder.decode(&[
+            IAC, WILL, NAWS, b'a', IAC, SB, NAWS, 0, 100, 0, IAC, IAC, IAC, SE, b'b',
+        ]);
+        assert!(matches!(events[0], TerminalEvent::Resize(100, 255)));
+        assert_eq!(codes(events), [KeyCode::Char('a'), KeyCode::Char('b')]);
+    }
+}
@@ -3,30 +3,266 @@ use crossterm::ExecutableCommand;
 #[derive(bevy::ecs::event::Event, Debug)]
 pub enum TerminalEvent {
     Key(crossterm::event::KeyEvent),
+    Mouse(crossterm::event::MouseEvent),
     Resize(u16, u16),
 }
 
+pub type Overlay = Box<dyn FnOnce(&mut ratatui::Frame, ratatui::layout::Rect) + Send + Sync>;
+
+/// Widgets to draw over the play area on the next render.
+#[derive(bevy::ecs::system::Resource, Default)]
+pub struct Overlays(Vec<Overlay>);
+
+impl Overlays {
+    pub fn push(
+        &mut self,
+        overlay: impl FnOnce(&mut ratatui::Frame, ratatui::layout::Rect) + Send + Sync + 'static,
+    ) {
+        self.0.push(Box::new(overlay));
+    }
+}
+
+/// The line along the bottom of the play area's border, such as each
+/// player's lives, if there is one.
+#[derive(bevy::ecs::system::Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hud(pub Option<String>);
+
+/// A rect of at most `width` by `height` in the middle of `area`.
+pub fn centered(area: ratatui::layout::Rect, width: u16, height: u16) -> ratatui::layout::Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    ratatui::layout::Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Where the play area was last drawn, in terminal cells.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayArea(pub ratatui::layout::Rect);
+
+impl PlayArea {
+    /// Map a terminal cell into frame coordinates, clamping cells outside
+    /// the play area to its edge. None until the first render.
+    pub fn frame_point(&self, column: u16, row: u16) -> Option<bevy::math::Vec2> {
+        let area = self.0;
+        if area.is_empty() {
+            return None;
+        }
+        let column = column.clamp(area.left(), area.right() - 1) - area.left();
+        let row = row.clamp(area.top(), area.bottom() - 1) - area.top();
+        Some(bevy::math::Vec2::new(column as f32, row as f32))
+    }
+}
+
+/// Output written to the terminal, to check what each frame costs.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Bytes written for the last frame drawn.
+    pub bytes_last_frame: u64,
+    pub bytes_total: u64,
+    pub frames_drawn: u64,
+    /// Frames which wrote nothing because nothing on screen changed.
+    pub frames_skipped: u64,
+}
+
+/// Counts the bytes written through it.
+pub struct CountingWriter<W> {
+    inner: W,
+    written: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl<W> CountingWriter<W> {
+    pub fn new(inner: W, written: std::sync::Arc<std::sync::atomic::AtomicU64>) -> Self {
+        Self { inner, written }
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written
+            .fetch_add(written as u64, std::sync::atomic::Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// What the attached terminal is able to report.
+#[derive(bevy::ecs::system::Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether the terminal speaks the kitty keyboard protocol, which is
+    /// needed for key release events.
+    pub keyboard_enhancement: bool,
+}
+
 pub fn plugin(app: &mut bevy::app::App) {
-    app.add_event::<TerminalEvent>();
-    app.insert_resource(Terminal::new().expect("error initialising terminal"));
+    let terminal = Terminal::new().expect("error initialising terminal");
+    app.insert_resource(terminal.capabilities);
+    app.insert_resource(terminal);
+    app.add_systems(bevy::app::Startup, check_size);
     app.add_systems(bevy::app::PreUpdate, handle_events);
+    drawing_plugin(app);
+}
+
+/// Drawing and resizing, without reading the terminal the game runs in, for
+/// a `Terminal` inserted along with whatever sends its `TerminalEvent`s.
+pub fn drawing_plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::IntoSystemConfigs;
+
+    app.add_event::<TerminalEvent>();
+    app.init_resource::<Overlays>();
+    app.init_resource::<Hud>();
+    app.init_resource::<PlayArea>();
+    app.init_resource::<Metrics>();
+    app.init_resource::<Fit>();
+    app.add_systems(bevy::app::PreUpdate, handle_resize.after(handle_events));
     app.add_systems(bevy::app::Last, render);
 }
 
+/// Where frames are written: stdout, or the socket of a hosted session.
+pub type Output = Box<dyn std::io::Write + Send + Sync>;
+
+/// Crossterm's backend, but for output which may not be the terminal the
+/// game runs in, and so knows its own size. Writes nothing at all for a
+/// frame in which no cell changed.
+pub struct Backend {
+    inner: ratatui::backend::CrosstermBackend<CountingWriter<Output>>,
+    /// Columns and rows, None to ask the terminal.
+    size: Option<(u16, u16)>,
+    /// Whether the cursor is known to be hidden already.
+    cursor_hidden: bool,
+}
+
+impl Backend {
+    /// A backend writing to `output`, adding what it writes to `written`.
+    pub fn new(
+        output: Output,
+        written: std::sync::Arc<std::sync::atomic::AtomicU64>,
+        size: Option<(u16, u16)>,
+    ) -> Self {
+        Self {
+            inner: ratatui::backend::CrosstermBackend::new(CountingWriter::new(output, written)),
+            size,
+            cursor_hidden: false,
+        }
+    }
+}
+
+impl std::io::Write for Backend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::Write::flush(&mut self.inner)
+    }
+}
+
+impl ratatui::backend::Backend for Backend {
+    fn draw<'a, I>(&mut self, content: I) -> std::io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a ratatui::buffer::Cell)>,
+    {
+        // crossterm's resets styles even when there's nothing to draw
+        let mut content = content.peekable();
+        if content.peek().is_none() {
+            return Ok(());
+        }
+        self.inner.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> std::io::Result<()> {
+        // ratatui hides it again every frame
+        if !self.cursor_hidden {
+            self.inner.hide_cursor()?;
+            self.cursor_hidden = true;
+        }
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> std::io::Result<()> {
+        self.cursor_hidden = false;
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> std::io::Result<(u16, u16)> {
+        self.inner.get_cursor()
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> std::io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn size(&self) -> std::io::Result<ratatui::layout::Rect> {
+        match self.size {
+            Some((width, height)) => Ok(ratatui::layout::Rect::new(0, 0, width, height)),
+            None => self.inner.size(),
+        }
+    }
+
+    fn window_size(&mut self) -> std::io::Result<ratatui::backend::WindowSize> {
+        match self.size {
+            Some((width, height)) => Ok(ratatui::backend::WindowSize {
+                columns_rows: ratatui::layout::Size { width, height },
+                pixels: ratatui::layout::Size::default(),
+            }),
+            None => self.inner.window_size(),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        ratatui::backend::Backend::flush(&mut self.inner)
+    }
+}
+
 #[derive(bevy::ecs::system::Resource)]
-pub struct Terminal(ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>);
+pub struct Terminal {
+    terminal: ratatui::Terminal<Backend>,
+    capabilities: Capabilities,
+    mouse_capture: bool,
+    written: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    /// The score and HUD shown by the last frame drawn, None to force a
+    /// redraw.
+    drawn_status: Option<(u32, Hud)>,
+    /// Whether this is the terminal the game runs in, which is put back
+    /// into cooked mode at the end.
+    local: bool,
+}
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        crossterm::execute!(
-            std::io::stdout(),
-            crossterm::event::PopKeyboardEnhancementFlags
-        )
-        .expect("pop keyboard extentions");
-        std::io::stdout()
-            .execute(crossterm::terminal::LeaveAlternateScreen)
-            .expect("leave terminal alternate screen");
-        crossterm::terminal::disable_raw_mode().expect("leave terminal raw mode");
+        let backend = self.terminal.backend_mut();
+        if self.mouse_capture {
+            crossterm::execute!(backend, crossterm::event::DisableMouseCapture)
+                .expect("disable mouse capture");
+        }
+        if self.capabilities.keyboard_enhancement {
+            crossterm::execute!(backend, crossterm::event::PopKeyboardEnhancementFlags)
+                .expect("pop keyboard extentions");
+        }
+        if self.local {
+            crossterm::execute!(backend, crossterm::terminal::LeaveAlternateScreen)
+                .expect("leave terminal alternate screen");
+            crossterm::terminal::disable_raw_mode().expect("leave terminal raw mode");
+        } else {
+            // the other end may well have gone already
+            let _ = crossterm::execute!(
+                backend,
+                crossterm::style::ResetColor,
+                crossterm::cursor::Show,
+                crossterm::terminal::LeaveAlternateScreen
+            );
+        }
     }
 }
 
@@ -34,113 +270,411 @@ impl Terminal {
     pub fn new() -> Result<Self, std::boxed::Box<dyn std::error::Error>> {
         crossterm::terminal::enable_raw_mode()?;
         std::io::stdout().execute(crossterm::terminal::EnterAlternateScreen)?;
+        let capabilities = Capabilities {
+            keyboard_enhancement: supports_keyboard_enhancement(),
+        };
+        if capabilities.keyboard_enhancement {
+            crossterm::execute!(
+                std::io::stdout(),
+                crossterm::event::PushKeyboardEnhancementFlags(
+                    crossterm::event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                        | crossterm::event::KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                        | crossterm::event::KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                        | crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                )
+            )?;
+        } else {
+            log::warn!("Terminal has no keyboard enhancement, falling back to key repeat timing");
+        }
+        Ok(Self::with_output(
+            Box::new(std::io::stdout()),
+            None,
+            capabilities,
+            true,
+        )?)
+    }
+
+    /// A terminal at the other end of `output`, such as a player's socket,
+    /// `width` by `height` cells until resized.
+    pub fn remote(mut output: Output, width: u16, height: u16) -> std::io::Result<Self> {
         crossterm::execute!(
-            std::io::stdout(),
-            crossterm::event::PushKeyboardEnhancementFlags(
-                crossterm::event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES
-                    | crossterm::event::KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
-                    | crossterm::event::KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
-                    | crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
-            )
+            output,
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
         )?;
-        let backend = ratatui::backend::CrosstermBackend::new(std::io::stdout());
-        Ok(Self(ratatui::Terminal::new(backend)?))
+        Self::with_output(
+            output,
+            Some((width, height)),
+            Capabilities::default(),
+            false,
+        )
+    }
+
+    fn with_output(
+        output: Output,
+        size: Option<(u16, u16)>,
+        capabilities: Capabilities,
+        local: bool,
+    ) -> std::io::Result<Self> {
+        let written = std::sync::Arc::default();
+        let backend = Backend::new(output, std::sync::Arc::clone(&written), size);
+        Ok(Self {
+            terminal: ratatui::Terminal::new(backend)?,
+            capabilities,
+            mouse_capture: false,
+            written,
+            drawn_status: None,
+            local,
+        })
+    }
+
+    /// Follow a remote terminal which has changed size.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().size = Some((width, height));
+    }
+
+    /// Ring the terminal bell, the only sound a terminal makes.
+    pub fn bell(&mut self) -> std::io::Result<()> {
+        use std::io::Write;
+        let writer = self.terminal.backend_mut();
+        writer.write_all(b"\x07")?;
+        writer.flush()
+    }
+
+    pub fn set_mouse_capture(&mut self, capture: bool) -> std::io::Result<()> {
+        let backend = self.terminal.backend_mut();
+        if capture {
+            crossterm::execute!(backend, crossterm::event::EnableMouseCapture)?;
+        } else {
+            crossterm::execute!(backend, crossterm::event::DisableMouseCapture)?;
+        }
+        self.mouse_capture = capture;
+        Ok(())
+    }
+}
+
+/// Query the terminal for kitty keyboard protocol support.
+///
+/// Must be called in raw mode. Terminals which don't answer the query at
+/// all, such as the Linux console, are treated as unsupported.
+pub fn supports_keyboard_enhancement() -> bool {
+    match crossterm::terminal::supports_keyboard_enhancement() {
+        Ok(supported) => supported,
+        Err(e) => {
+            log::warn!("Couldn't query keyboard enhancement support: {}", e);
+            false
+        }
+    }
+}
+
+/// Whether the play area is drawn in colour.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourMode {
+    #[default]
+    Colour,
+    /// Only the terminal's default colours, for terminals or eyes which
+    /// don't get on with the palette.
+    Monochrome,
+}
+
+impl ColourMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColourMode::Colour => "colour",
+            ColourMode::Monochrome => "monochrome",
+        }
     }
 }
 
-struct LevelWidget<'a>(&'a crate::buffer::Buffer);
+fn to_ratatui(color: crate::buffer::Color) -> ratatui::style::Color {
+    use crate::buffer::Color;
+    match color {
+        Color::Reset => ratatui::style::Color::Reset,
+        Color::Black => ratatui::style::Color::Black,
+        Color::Red => ratatui::style::Color::Red,
+        Color::Green => ratatui::style::Color::Green,
+        Color::Yellow => ratatui::style::Color::Yellow,
+        Color::Blue => ratatui::style::Color::Blue,
+        Color::Magenta => ratatui::style::Color::Magenta,
+        Color::Cyan => ratatui::style::Color::Cyan,
+        Color::Gray => ratatui::style::Color::Gray,
+        Color::DarkGray => ratatui::style::Color::DarkGray,
+        Color::LightRed => ratatui::style::Color::LightRed,
+        Color::LightGreen => ratatui::style::Color::LightGreen,
+        Color::LightYellow => ratatui::style::Color::LightYellow,
+        Color::LightBlue => ratatui::style::Color::LightBlue,
+        Color::LightMagenta => ratatui::style::Color::LightMagenta,
+        Color::LightCyan => ratatui::style::Color::LightCyan,
+        Color::White => ratatui::style::Color::White,
+    }
+}
+
+struct LevelWidget<'a>(&'a crate::buffer::Buffer, ColourMode);
 
 impl<'a> ratatui::widgets::Widget for LevelWidget<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
     where
         Self: Sized,
     {
-        let shape = self.0 .0.shape();
-        use itertools::Itertools;
-        for (row, col) in (0..shape[0]).cartesian_product(0..shape[1]) {
-            let cell = self.0 .0[[row, col]];
-            if col >= area.width.into() || row >= area.height.into() {
-                continue;
-            }
-            buf.content[buf.area.width as usize * (row + area.y as usize) + col + area.x as usize] = {
+        // never write outside the terminal, however small it gets
+        let area = area.intersection(buf.area);
+        let (rows, columns) = self.0 .0.dim();
+        let columns = columns.min(area.width.into());
+        for row in 0..rows.min(area.height.into()) {
+            let y = area.y + row as u16;
+            let mut col = 0;
+            while col < columns {
+                let x = area.x + col as u16;
+                let cell = &self.0 .0[[row, col]];
                 let mut rat_cell = ratatui::buffer::Cell::default();
-                if let Some(c) = cell.character {
-                    rat_cell.set_char(c);
+                if self.1 == ColourMode::Colour {
+                    rat_cell
+                        .set_fg(to_ratatui(cell.fg))
+                        .set_bg(to_ratatui(cell.bg));
                 }
-                rat_cell
+                let mut width = 1;
+                match cell.character {
+                    // a continuation whose glyph was drawn over stays blank
+                    Some(glyph) if glyph.is_continuation() => {}
+                    // as is a double width glyph with no room for its right half
+                    Some(glyph) if glyph.width() == 2 && col + 1 == columns => {}
+                    Some(glyph) => {
+                        rat_cell.set_symbol(glyph.as_str());
+                        width = glyph.width().clamp(1, 2);
+                    }
+                    None => {}
+                }
+                *buf.get_mut(x, y) = rat_cell;
+                if width == 2 {
+                    // the terminal draws the right half over this one
+                    buf.get_mut(x + 1, y).reset();
+                }
+                col += width;
             }
         }
     }
 }
 
-fn fallible_render(terminal: &mut Terminal, buffer: &crate::buffer::Buffer) -> std::io::Result<()> {
-    terminal.0.draw(|frame| {
-        let frame_size = ratatui::layout::Rect::new(
-            0,
-            0,
-            crate::frame::WIDTH as u16,
-            crate::frame::HEIGHT as u16,
-        );
-        let border_length = 2;
-        let main_layout_vertical = ratatui::layout::Layout::new(
-            ratatui::layout::Direction::Vertical,
-            [
-                ratatui::layout::Constraint::Fill(1),
-                ratatui::layout::Constraint::Length(frame_size.height + border_length),
-                ratatui::layout::Constraint::Fill(1),
-            ],
-        )
-        .split(frame.size());
-        let main_layout = ratatui::layout::Layout::new(
-            ratatui::layout::Direction::Horizontal,
-            [
-                ratatui::layout::Constraint::Fill(1),
-                ratatui::layout::Constraint::Length(frame_size.width + border_length),
-                ratatui::layout::Constraint::Fill(1),
-            ],
-        )
-        .split(main_layout_vertical[1]);
-        let block = ratatui::widgets::Block::default()
-            .title("Shellaga")
-            .borders(ratatui::widgets::Borders::ALL);
-        let inner_size = block.inner(main_layout[1]);
-        frame.render_widget(block, main_layout[1]);
-        frame.render_widget(LevelWidget(&*buffer), inner_size);
+pub const BORDER_LENGTH: u16 = 2;
+
+/// The smallest terminal which fits the play area and its border.
+pub fn required_size(size: &crate::frame::Size) -> (u16, u16) {
+    (
+        size.width as u16 + BORDER_LENGTH,
+        size.height as u16 + BORDER_LENGTH,
+    )
+}
+
+pub fn fits(size: &crate::frame::Size, width: u16, height: u16) -> bool {
+    let (required_width, required_height) = required_size(size);
+    width >= required_width && height >= required_height
+}
+
+/// Draw a frame, returning where the play area went.
+pub fn fallible_render(
+    terminal: &mut ratatui::Terminal<impl ratatui::backend::Backend>,
+    size: &crate::frame::Size,
+    buffer: &crate::buffer::Buffer,
+    score: &crate::score::Score,
+    hud: &Hud,
+    colour: ColourMode,
+    overlays: Vec<Overlay>,
+) -> std::io::Result<ratatui::layout::Rect> {
+    let mut play_area = ratatui::layout::Rect::default();
+    terminal.draw(|frame| {
+        play_area = draw(frame, size, buffer, score, hud, colour, overlays);
     })?;
-    Ok(())
+    Ok(play_area)
 }
 
-fn render(
+/// Draw the play area centred in the frame, returning where it went, or
+/// a message asking for a bigger terminal if it doesn't fit.
+fn draw(
+    frame: &mut ratatui::Frame,
+    field: &crate::frame::Size,
+    buffer: &crate::buffer::Buffer,
+    score: &crate::score::Score,
+    hud: &Hud,
+    colour: ColourMode,
+    overlays: Vec<Overlay>,
+) -> ratatui::layout::Rect {
+    let size = frame.size();
+    if !fits(field, size.width, size.height) {
+        let (width, height) = required_size(field);
+        let message = ratatui::widgets::Paragraph::new(format!(
+            "Terminal too small, need {}x{}\nCurrently {}x{}",
+            width, height, size.width, size.height
+        ))
+        .alignment(ratatui::layout::Alignment::Center)
+        .wrap(ratatui::widgets::Wrap { trim: true });
+        frame.render_widget(message, centered(size, size.width, 4));
+        return ratatui::layout::Rect::default();
+    }
+
+    let (width, height) = required_size(field);
+    let area = centered(size, width, height);
+    let mut block = ratatui::widgets::Block::default()
+        .title("Shellaga")
+        .title(
+            ratatui::widgets::block::Title::from(format!("Score: {}", score.total()))
+                .alignment(ratatui::layout::Alignment::Right),
+        )
+        .borders(ratatui::widgets::Borders::ALL);
+    if let Some(hud) = &hud.0 {
+        block = block.title(
+            ratatui::widgets::block::Title::from(hud.as_str())
+                .position(ratatui::widgets::block::Position::Bottom),
+        );
+    }
+    let inner_size = block.inner(area);
+    frame.render_widget(block, area);
+    frame.render_widget(LevelWidget(buffer, colour), inner_size);
+    for overlay in overlays {
+        overlay(frame, inner_size);
+    }
+    inner_size
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render(
     mut terminal: bevy::ecs::system::ResMut<Terminal>,
+    size: bevy::ecs::system::Res<crate::frame::Size>,
     buffer: bevy::ecs::system::Res<crate::buffer::Buffer>,
+    score: bevy::ecs::system::Res<crate::score::Score>,
+    hud: bevy::ecs::system::Res<Hud>,
+    config: bevy::ecs::system::Res<crate::config::Config>,
+    mut overlays: bevy::ecs::system::ResMut<Overlays>,
+    mut play_area: bevy::ecs::system::ResMut<PlayArea>,
+    mut damage: bevy::ecs::system::ResMut<crate::buffer::Damage>,
+    mut metrics: bevy::ecs::system::ResMut<Metrics>,
+) {
+    use bevy::ecs::change_detection::DetectChanges;
+
+    damage.update(&buffer);
+    let overlays = std::mem::take(&mut overlays.0);
+    // a static scene doesn't need drawing at all, and one with overlays is
+    // drawn but not written unless a cell changed
+    let score = &*score;
+    if damage.dirty.is_none()
+        && overlays.is_empty()
+        && matches!(&terminal.drawn_status, Some((drawn, drawn_hud))
+            if *drawn == score.total() && drawn_hud == &*hud)
+        && !config.is_changed()
+    {
+        metrics.frames_skipped += 1;
+        return;
+    }
+
+    match fallible_render(
+        &mut terminal.terminal,
+        &size,
+        &buffer,
+        score,
+        &hud,
+        config.colour,
+        overlays,
+    ) {
+        Ok(area) => {
+            play_area.0 = area;
+            terminal.drawn_status = Some((score.total(), hud.clone()));
+            damage.drawn(&buffer);
+        }
+        Err(_) => log::error!("Failed to render frame"),
+    }
+
+    let written = terminal
+        .written
+        .swap(0, std::sync::atomic::Ordering::Relaxed);
+    if written == 0 {
+        metrics.frames_skipped += 1;
+        return;
+    }
+    metrics.bytes_last_frame = written;
+    metrics.bytes_total += written;
+    metrics.frames_drawn += 1;
+    log::trace!("Wrote {} bytes, dirty {:?}", written, damage.dirty);
+}
+
+/// Pauses the game while the terminal is too small to show it.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+struct Fit {
+    too_small: bool,
+    paused: bool,
+}
+
+impl Fit {
+    fn update(
+        &mut self,
+        size: &crate::frame::Size,
+        width: u16,
+        height: u16,
+        time: &mut bevy::time::Time<bevy::time::Virtual>,
+    ) {
+        let too_small = !fits(size, width, height);
+        if too_small == self.too_small {
+            return;
+        }
+        self.too_small = too_small;
+        if too_small {
+            log::warn!("Terminal too small at {}x{}, pausing", width, height);
+            self.paused = !time.is_paused();
+            time.pause();
+        } else if std::mem::take(&mut self.paused) {
+            log::info!("Terminal fits at {}x{}, resuming", width, height);
+            time.unpause();
+        }
+    }
+}
+
+fn check_size(
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    mut fit: bevy::ecs::system::ResMut<Fit>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
+) {
+    match crossterm::terminal::size() {
+        Ok((width, height)) => fit.update(&size, width, height, &mut time),
+        Err(e) => log::error!("Couldn't read terminal size: {}", e),
+    }
+}
+
+fn handle_resize(
+    mut reader: bevy::ecs::event::EventReader<TerminalEvent>,
+    mut terminal: bevy::ecs::system::ResMut<Terminal>,
+    size: bevy::ecs::system::Res<crate::frame::Size>,
+    mut fit: bevy::ecs::system::ResMut<Fit>,
+    mut time: bevy::ecs::system::ResMut<bevy::time::Time<bevy::time::Virtual>>,
 ) {
-    if let Err(_) = fallible_render(&mut *terminal, &*buffer) {
-        log::error!("Failed to render frame");
+    for event in reader.read() {
+        if let TerminalEvent::Resize(width, height) = *event {
+            log::info!("Terminal resized to {}x{}", width, height);
+            terminal.drawn_status = None;
+            fit.update(&size, width, height, &mut time);
+        }
     }
 }
 
-fn handle_events(mut event_sender: bevy::ecs::event::EventWriter<TerminalEvent>) {
+pub fn handle_events(mut event_sender: bevy::ecs::event::EventWriter<TerminalEvent>) {
     while let Ok(true) = crossterm::event::poll(std::time::Duration::from_millis(0)) {
-        match crossterm::event::read() {
-            Ok(e) => {
-                log::trace!("crossterm event {:?}", e);
-                match e {
-                    // forward crossterm events into bevy
-                    crossterm::event::Event::Key(key_event) => {
-                        event_sender.send(TerminalEvent::Key(key_event));
-                    }
-                    crossterm::event::Event::Resize(w, h) => {
-                        event_sender.send(TerminalEvent::Resize(w, h));
-                    }
-                    // ignore these for now
-                    crossterm::event::Event::FocusGained => {}
-                    crossterm::event::Event::FocusLost => {}
-                    crossterm::event::Event::Mouse(_) => {}
-                    crossterm::event::Event::Paste(_) => {}
+        if let Ok(e) = crossterm::event::read() {
+            log::trace!("crossterm event {:?}", e);
+            match e {
+                // forward crossterm events into bevy
+                crossterm::event::Event::Key(key_event) => {
+                    event_sender.send(TerminalEvent::Key(key_event));
+                }
+                crossterm::event::Event::Mouse(mouse_event) => {
+                    event_sender.send(TerminalEvent::Mouse(mouse_event));
                 }
+                crossterm::event::Event::Resize(w, h) => {
+                    event_sender.send(TerminalEvent::Resize(w, h));
+                }
+                // ignore these for now
+                crossterm::event::Event::FocusGained => {}
+                crossterm::event::Event::FocusLost => {}
+                crossterm::event::Event::Paste(_) => {}
             }
-            _ => {}
-        };
+        }
     }
 }
 
@@ -149,13 +683,188 @@ mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    fn draw_to(width: u16, height: u16) -> ratatui::Terminal<ratatui::backend::TestBackend> {
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+        let size = crate::frame::Size::default();
+        let buffer = crate::buffer::Buffer(ndarray::Array2::from_elem(
+            (size.height, size.width),
+            crate::buffer::Cell::default(),
+        ));
+        terminal
+            .draw(|frame| {
+                draw(
+                    frame,
+                    &size,
+                    &buffer,
+                    &crate::score::Score::default(),
+                    &Hud::default(),
+                    ColourMode::Colour,
+                    Vec::new(),
+                );
+            })
+            .unwrap();
+        terminal
+    }
+
+    #[test]
+    fn small_terminals_ask_for_more_room() {
+        for (width, height) in [(0, 0), (1, 1), (80, 24), (200, 10)] {
+            let terminal = draw_to(width, height);
+            let text: String = terminal
+                .backend()
+                .buffer()
+                .content
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect();
+            assert_eq!(
+                text.contains("Terminal too small"),
+                width >= 18,
+                "{}x{}",
+                width,
+                height
+            );
+        }
+    }
+
+    #[test]
+    fn play_area_is_centred() {
+        let mut terminal = draw_to(140, 40);
+        let mut play_area = ratatui::layout::Rect::default();
+        terminal
+            .draw(|frame| {
+                play_area = draw(
+                    frame,
+                    &crate::frame::Size::default(),
+                    &crate::buffer::Buffer::default(),
+                    &crate::score::Score::default(),
+                    &Hud(Some("P1 ♥♥♥ 0".to_string())),
+                    ColourMode::Colour,
+                    Vec::new(),
+                );
+            })
+            .unwrap();
+        assert_eq!(play_area, ratatui::layout::Rect::new(6, 4, 128, 32));
+        // the HUD goes in the border, not over the play area
+        let border: String = (play_area.x..play_area.right())
+            .map(|x| {
+                terminal
+                    .backend()
+                    .buffer()
+                    .get(x, play_area.bottom())
+                    .symbol()
+                    .to_string()
+            })
+            .collect();
+        assert!(border.starts_with("P1 ♥♥♥ 0─"));
+    }
+
+    #[test]
+    fn static_scene_writes_nothing() {
+        let written = std::sync::Arc::<std::sync::atomic::AtomicU64>::default();
+        let backend = Backend::new(
+            Box::new(std::io::sink()),
+            std::sync::Arc::clone(&written),
+            None,
+        );
+        let mut terminal = ratatui::Terminal::with_options(
+            backend,
+            ratatui::TerminalOptions {
+                viewport: ratatui::Viewport::Fixed(ratatui::layout::Rect::new(0, 0, 140, 40)),
+            },
+        )
+        .unwrap();
+        let size = crate::frame::Size::default();
+        let mut buffer = crate::buffer::Buffer(ndarray::Array2::from_elem(
+            (size.height, size.width),
+            crate::buffer::Cell::default(),
+        ));
+        buffer.0[[4, 8]].character = Some('*'.into());
+        let score = crate::score::Score::default();
+        let mut frame_bytes = |message: &'static str| {
+            let overlay: Overlay = Box::new(move |frame, area| {
+                frame.render_widget(ratatui::widgets::Paragraph::new(message), area)
+            });
+            fallible_render(
+                &mut terminal,
+                &size,
+                &buffer,
+                &score,
+                &Hud::default(),
+                ColourMode::Colour,
+                vec![overlay],
+            )
+            .unwrap();
+            written.swap(0, std::sync::atomic::Ordering::Relaxed)
+        };
+
+        assert!(frame_bytes("Paused") > 1000);
+        // the same overlay again changes nothing
+        assert_eq!(frame_bytes("Paused"), 0);
+        assert!(frame_bytes("Resumed") > 0);
+    }
+
+    #[test]
+    fn shrinking_pauses_until_it_fits() {
+        let mut time = bevy::time::Time::<bevy::time::Virtual>::default();
+        let mut fit = Fit::default();
+        let size = crate::frame::Size::default();
+        fit.update(&size, 80, 24, &mut time);
+        assert!(time.is_paused());
+        fit.update(&size, 200, 50, &mut time);
+        assert!(!time.is_paused());
+
+        // stays paused if the player had already paused
+        time.pause();
+        fit.update(&size, 80, 24, &mut time);
+        fit.update(&size, 200, 50, &mut time);
+        assert!(time.is_paused());
+    }
+
+    #[test]
+    fn pointer_maps_into_play_area() {
+        let play_area = PlayArea(ratatui::layout::Rect::new(10, 5, 128, 32));
+        assert_eq!(
+            play_area.frame_point(10, 5),
+            Some(bevy::math::Vec2::new(0.0, 0.0))
+        );
+        assert_eq!(
+            play_area.frame_point(20, 8),
+            Some(bevy::math::Vec2::new(10.0, 3.0))
+        );
+        // outside the border clamps to the nearest edge
+        assert_eq!(
+            play_area.frame_point(0, 100),
+            Some(bevy::math::Vec2::new(0.0, 31.0))
+        );
+        assert_eq!(PlayArea::default().frame_point(0, 0), None);
+    }
+
+    #[test]
+    fn render_wide_glyphs() {
+        let sprite = crate::sprite::Sprite::from_lines(&["a中b", "中中"]).unwrap();
+        let mut terminal_buffer =
+            ratatui::buffer::Buffer::empty(ratatui::layout::Rect::new(0, 0, 3, 2));
+
+        use ratatui::widgets::Widget;
+        LevelWidget(&sprite.buffer, ColourMode::Colour)
+            .render(terminal_buffer.area, &mut terminal_buffer);
+
+        // the second row has no room for the right half of its last glyph
+        assert_eq!(
+            terminal_buffer,
+            ratatui::buffer::Buffer::with_lines(vec!["a中", "中 "])
+        );
+    }
+
     #[test]
     fn render_top_left() {
         let buffer = crate::buffer::Buffer(ndarray::array![['x']].map(|c| crate::buffer::Cell {
-            character: Some(*c),
+            character: Some((*c).into()),
             ..Default::default()
         }));
-        let widget = LevelWidget(&buffer);
+        let widget = LevelWidget(&buffer, ColourMode::Colour);
         let mut terminal_buffer = ratatui::buffer::Buffer {
             area: ratatui::layout::Rect {
                 x: 0,
@@ -183,13 +892,33 @@ mod tests {
         assert_eq!(terminal_buffer, expected);
     }
 
+    #[test]
+    fn colours_follow_mode() {
+        use ratatui::widgets::Widget;
+        let buffer = crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
+            character: Some('*'.into()),
+            fg: crate::buffer::Color::Magenta,
+            ..Default::default()
+        }]]);
+        let area = ratatui::layout::Rect::new(0, 0, 1, 1);
+
+        let mut coloured = ratatui::buffer::Buffer::empty(area);
+        LevelWidget(&buffer, ColourMode::Colour).render(area, &mut coloured);
+        assert_eq!(coloured.get(0, 0).fg, ratatui::style::Color::Magenta);
+        assert_eq!(coloured.get(0, 0).bg, ratatui::style::Color::Reset);
+
+        let mut plain = ratatui::buffer::Buffer::empty(area);
+        LevelWidget(&buffer, ColourMode::Monochrome).render(area, &mut plain);
+        assert_eq!(plain, ratatui::buffer::Buffer::with_lines(vec!["*"]));
+    }
+
     #[test]
     fn render_translated() {
         let buffer = crate::buffer::Buffer(ndarray::array![['x']].map(|c| crate::buffer::Cell {
-            character: Some(*c),
+            character: Some((*c).into()),
             ..Default::default()
         }));
-        let widget = LevelWidget(&buffer);
+        let widget = LevelWidget(&buffer, ColourMode::Colour);
         let mut terminal_buffer = ratatui::buffer::Buffer {
             area: ratatui::layout::Rect {
                 x: 0,
@@ -222,12 +951,12 @@ mod tests {
         let buffer = crate::buffer::Buffer(
             ndarray::array![[Some('x'), Some('x')], [None, Some('x')]].map(|c| {
                 crate::buffer::Cell {
-                    character: *c,
+                    character: c.map(crate::buffer::Glyph::from),
                     ..Default::default()
                 }
             }),
         );
-        let widget = LevelWidget(&buffer);
+        let widget = LevelWidget(&buffer, ColourMode::Colour);
         let mut terminal_buffer = ratatui::buffer::Buffer {
             area: ratatui::layout::Rect {
                 x: 0,
@@ -265,12 +994,12 @@ mod tests {
         let buffer = crate::buffer::Buffer(
             ndarray::array![[Some('x'), Some('x')], [None, Some('x')]].map(|c| {
                 crate::buffer::Cell {
-                    character: *c,
+                    character: c.map(crate::buffer::Glyph::from),
                     ..Default::default()
                 }
             }),
         );
-        let widget = LevelWidget(&buffer);
+        let widget = LevelWidget(&buffer, ColourMode::Colour);
         let mut terminal_buffer = ratatui::buffer::Buffer {
             area: ratatui::layout::Rect {
                 x: 0,
@@ -308,12 +1037,12 @@ mod tests {
         let buffer = crate::buffer::Buffer(
             ndarray::array![[Some('x'), Some('x')], [None, Some('x')]].map(|c| {
                 crate::buffer::Cell {
-                    character: *c,
+                    character: c.map(crate::buffer::Glyph::from),
                     ..Default::default()
                 }
             }),
         );
-        let widget = LevelWidget(&buffer);
+        let widget = LevelWidget(&buffer, ColourMode::Colour);
         let mut terminal_buffer = ratatui::buffer::Buffer {
             area: ratatui::layout::Rect {
                 x: 0,
@@ -0,0 +1,123 @@
+use crate::app::Screen;
+use crate::input::Action;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Item {
+    Play,
+    CoOp,
+    HighScores,
+    Settings,
+    Quit,
+}
+
+impl Item {
+    const ALL: [Item; 5] = [
+        Item::Play,
+        Item::CoOp,
+        Item::HighScores,
+        Item::Settings,
+        Item::Quit,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Item::Play => "Play",
+            Item::CoOp => "Co-op",
+            Item::HighScores => "High Scores",
+            Item::Settings => "Settings",
+            Item::Quit => "Quit",
+        }
+    }
+}
+
+/// The menu shown before and between games.
+#[derive(bevy::ecs::system::Resource, Debug, Default)]
+pub struct Title {
+    selected: usize,
+}
+
+pub fn plugin(app: &mut bevy::app::App) {
+    use bevy::ecs::schedule::{common_conditions::in_state, IntoSystemConfigs};
+
+    app.init_resource::<Title>();
+    app.add_systems(bevy::app::Update, navigate.run_if(in_state(Screen::Title)));
+    app.add_systems(
+        bevy::app::PostUpdate,
+        render.run_if(in_state(Screen::Title)),
+    );
+}
+
+fn navigate(
+    mut reader: bevy::ecs::event::EventReader<crate::input::ActionEvent>,
+    mut title: bevy::ecs::system::ResMut<Title>,
+    mut players: bevy::ecs::system::ResMut<crate::player::Players>,
+    mut next_screen: bevy::ecs::system::ResMut<bevy::ecs::schedule::NextState<Screen>>,
+    mut exit: bevy::ecs::event::EventWriter<bevy::app::AppExit>,
+) {
+    for event in reader.read().filter(|event| event.pressed) {
+        match event.action {
+            Action::MoveUp => {
+                title.selected = (title.selected + Item::ALL.len() - 1) % Item::ALL.len();
+            }
+            Action::MoveDown => title.selected = (title.selected + 1) % Item::ALL.len(),
+            Action::Fire => match Item::ALL[title.selected] {
+                Item::Play => {
+                    players.0 = 1;
+                    next_screen.set(Screen::Playing);
+                }
+                Item::CoOp => {
+                    players.0 = crate::player::MAX_PLAYERS;
+                    next_screen.set(Screen::Playing);
+                }
+                Item::HighScores => next_screen.set(Screen::HighScores),
+                Item::Settings => next_screen.set(Screen::Settings),
+                Item::Quit => {
+                    exit.send(bevy::app::AppExit);
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+fn render(
+    title: bevy::ecs::system::Res<Title>,
+    mode: bevy::ecs::system::Res<crate::level::Mode>,
+    mut overlays: bevy::ecs::system::ResMut<crate::terminal::Overlays>,
+) {
+    let selected = title.selected;
+    let subtitle = format!("{:?}", *mode);
+
+    overlays.push(move |frame, area| {
+        use ratatui::style::Stylize;
+
+        let mut lines = vec![
+            ratatui::text::Line::raw("S H E L L A G A").bold(),
+            ratatui::text::Line::raw(subtitle),
+            ratatui::text::Line::raw(""),
+        ];
+        lines.extend(Item::ALL.iter().enumerate().map(|(i, item)| {
+            let line = ratatui::text::Line::raw(item.name());
+            if i == selected {
+                line.reversed()
+            } else {
+                line
+            }
+        }));
+        let area = crate::terminal::centered(area, 30, lines.len() as u16 + 2);
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            ratatui::widgets::Paragraph::new(lines)
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(
+                    ratatui::widgets::Block::default()
+                        .title(
+                            ratatui::widgets::block::Title::from(" Fire to select ")
+                                .position(ratatui::widgets::block::Position::Bottom),
+                        )
+                        .borders(ratatui::widgets::Borders::ALL),
+                ),
+            area,
+        );
+    });
+}
@@ -0,0 +1,93 @@
+//! `shellaga watch`, which shows a game streamed by `--spectate` without
+//! taking part in it.
+
+use std::io::{Read, Write};
+
+/// How long to wait on the keyboard before checking for more of the game.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(20);
+
+/// Puts the terminal back however watching ends.
+struct Screen;
+
+impl Screen {
+    fn enter() -> std::io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+            crossterm::cursor::Hide
+        )?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::style::ResetColor,
+            crossterm::cursor::Show,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Show the game streamed at `address` until it ends, or `q` or `Esc` is
+/// pressed.
+pub fn run(address: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stream = crate::spectate::connect(address)?;
+    log::info!("Watching {}", address);
+    let screen = Screen::enter()?;
+    let mut stdout = std::io::stdout().lock();
+    let mut chunk = [0; 16 * 1024];
+    let ended = loop {
+        // only wait on the keyboard while there's nothing to show
+        let wait = match stream.read(&mut chunk) {
+            Ok(0) => break "The game has ended",
+            Ok(read) => {
+                stdout.write_all(&chunk[..read])?;
+                stdout.flush()?;
+                std::time::Duration::ZERO
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => POLL_INTERVAL,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                log::error!("Lost the game: {}", e);
+                break "Lost the game";
+            }
+        };
+
+        if !crossterm::event::poll(wait)? {
+            continue;
+        }
+        match crossterm::event::read()? {
+            crossterm::event::Event::Key(key) if is_quit(&key) => {
+                drop(screen);
+                return Ok(());
+            }
+            crossterm::event::Event::Resize(..) => {
+                crossterm::execute!(
+                    stdout,
+                    crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+                )?;
+                // ask for the whole frame again
+                let _ = stream.write_all(b"\n");
+            }
+            _ => {}
+        }
+    };
+    drop(screen);
+    println!("{}", ended);
+    Ok(())
+}
+
+fn is_quit(key: &crossterm::event::KeyEvent) -> bool {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
@@ -13,6 +13,12 @@ pub fn plugin(app: &mut bevy::app::App) {
         bevy::app::Update,
         update_shots.run_if(any_with_component::<Shot>),
     );
+    app.add_systems(
+        bevy::app::Update,
+        handle_shot_hits
+            .after(crate::collider::CollisionDetection)
+            .run_if(any_with_component::<Shot>),
+    );
     app.add_systems(
         bevy::app::Update,
         despawn_shots
@@ -21,10 +27,58 @@ pub fn plugin(app: &mut bevy::app::App) {
     );
 }
 
+/// Speed of a shot in cells per second.
+const SHOT_SPEED: f32 = 40.0;
+/// Angle between the shots of a spread, in radians.
+const SPREAD_ANGLE: f32 = 0.25;
+
+/// The shots fired each time a weapon goes off.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pattern {
+    /// One shot straight ahead
+    #[default]
+    Single,
+    /// Three shots fanning out
+    Spread,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 2] = [Pattern::Single, Pattern::Spread];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Single => "single",
+            Pattern::Spread => "spread",
+        }
+    }
+
+    /// Direction of each shot, in radians from straight ahead.
+    fn angles(&self) -> &'static [f32] {
+        match self {
+            Pattern::Single => &[0.0],
+            Pattern::Spread => &[-SPREAD_ANGLE, 0.0, SPREAD_ANGLE],
+        }
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::ALL
+            .into_iter()
+            .find(|pattern| pattern.name() == s)
+            .ok_or_else(|| format!("unknown weapon \"{}\"", s))
+    }
+}
+
 #[derive(bevy::ecs::component::Component)]
 pub struct Weapon {
+    /// Seconds to reload before difficulty scaling.
+    reload_duration: f32,
     reload_timer: bevy::time::Timer,
     trigger: bool,
+    pub pattern: Pattern,
 }
 
 impl Weapon {
@@ -38,11 +92,13 @@ impl Weapon {
 
     pub fn new(reload_duration: f32) -> Self {
         Self {
+            reload_duration,
             reload_timer: bevy::time::Timer::from_seconds(
                 reload_duration,
                 bevy::time::TimerMode::Once,
             ),
             trigger: false,
+            pattern: Pattern::default(),
         }
     }
 }
@@ -52,15 +108,20 @@ pub struct LazerShot;
 
 #[derive(bevy::ecs::component::Component)]
 pub struct Shot {
-    speed: f32,
+    velocity: bevy::math::f32::Vec2,
+    /// The player who fired it.
+    pub owner: usize,
 }
 
 fn reload_weapons_system(
     mut commands: bevy::ecs::system::Commands,
     time: bevy::ecs::system::Res<bevy::time::Time>,
+    scaling: bevy::ecs::system::Res<crate::difficulty::Scaling>,
+    mut performance: bevy::ecs::system::ResMut<crate::difficulty::Performance>,
     mut query: bevy::ecs::system::Query<(
         &mut Weapon,
         &bevy::transform::components::GlobalTransform,
+        &crate::player::Player,
     )>,
     mut frame_query: bevy::ecs::system::Query<
         (
@@ -77,7 +138,11 @@ fn reload_weapons_system(
 
     let frame_inverse = frame_transform.compute_matrix().inverse();
 
-    for (mut weapon, weapon_transform) in query.iter_mut() {
+    for (mut weapon, weapon_transform, player) in query.iter_mut() {
+        let reload = weapon.reload_duration * scaling.reload;
+        weapon
+            .reload_timer
+            .set_duration(std::time::Duration::from_secs_f32(reload));
         weapon.reload_timer.tick(time.delta());
 
         if weapon.reload_timer.just_finished() {
@@ -89,16 +154,22 @@ fn reload_weapons_system(
         }
 
         log::trace!("Firing weapon");
-        shoot_lazer(
-            &mut commands,
-            frame_entity,
-            // shots are parented to the frame but begin at the position
-            // of the weaon so we must map the transforms from weapon space
-            // to frame space
-            bevy::transform::components::Transform::from_matrix(
-                weapon_transform.compute_matrix() * frame_inverse,
-            ),
+        // shots are parented to the frame but begin at the position
+        // of the weaon so we must map the transforms from weapon space
+        // to frame space
+        let transform = bevy::transform::components::Transform::from_matrix(
+            weapon_transform.compute_matrix() * frame_inverse,
         );
+        for angle in weapon.pattern.angles() {
+            shoot_lazer(
+                &mut commands,
+                frame_entity,
+                transform,
+                bevy::math::f32::Vec2::from_angle(*angle) * SHOT_SPEED,
+                player.0,
+            );
+            performance.record_shot();
+        }
         weapon.reload_timer.reset();
     }
 }
@@ -108,8 +179,28 @@ fn update_shots(
     mut query: bevy::ecs::system::Query<(&Shot, &mut bevy::transform::components::Transform)>,
 ) {
     for (shot, mut transform) in &mut query {
-        transform.translation +=
-            bevy::math::f32::Vec3::new(1.0, 0.0, 0.0) * shot.speed * time.delta_seconds();
+        transform.translation += shot.velocity.extend(0.0) * time.delta_seconds();
+    }
+}
+
+fn handle_shot_hits(
+    mut commands: bevy::ecs::system::Commands,
+    mut reader: bevy::ecs::event::EventReader<crate::collider::CollisionEvent>,
+    shot_query: bevy::ecs::system::Query<(), bevy::ecs::query::With<Shot>>,
+    enemy_query: bevy::ecs::system::Query<(), bevy::ecs::query::With<crate::enemy::Enemy>>,
+    mut performance: bevy::ecs::system::ResMut<crate::difficulty::Performance>,
+) {
+    let mut spent = std::collections::HashSet::new();
+    for event in reader.read() {
+        let Some((shot, _)) =
+            event.matching(|e| shot_query.contains(e), |e| enemy_query.contains(e))
+        else {
+            continue;
+        };
+        if spent.insert(shot) {
+            commands.entity(shot).despawn();
+            performance.record_hit();
+        }
     }
 }
 
@@ -166,19 +257,30 @@ fn shoot_lazer(
     commands: &mut bevy::ecs::system::Commands,
     frame: bevy::ecs::entity::Entity,
     transform: bevy::transform::components::Transform,
+    velocity: bevy::math::f32::Vec2,
+    owner: usize,
 ) {
     commands
         .spawn((
             LazerShot,
-            Shot { speed: 40.0 },
+            Shot { velocity, owner },
             crate::sprite::Sprite {
                 buffer: crate::buffer::Buffer(ndarray::array![[crate::buffer::Cell {
-                    character: Some('-'),
+                    character: Some('-'.into()),
                     depth: 1.0, //  behind player
                     ..Default::default()
                 },]]),
             },
+            crate::subcell::Particle {
+                fg: crate::buffer::Color::White,
+                depth: 1.0,
+            },
             crate::collider::Collider::new(1.0, 1.0),
+            crate::collider::CollisionLayers::new(
+                crate::collider::layer::SHOT,
+                crate::collider::layer::ENEMY,
+            ),
+            crate::collider::Sweep::default(),
             bevy::transform::TransformBundle::from_transform(transform),
         ))
         .set_parent(frame);